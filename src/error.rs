@@ -0,0 +1,49 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ExtractError {
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    #[error("failed to build rustdoc JSON for package `{package}`")]
+    Build {
+        package: String,
+        #[source]
+        source: rustdoc_json::BuildError,
+    },
+
+    #[error(
+        "rustdoc JSON of package `{package}` has format version {found}, but {required} is required"
+    )]
+    FormatVersion {
+        package: String,
+        found: u32,
+        required: u32,
+    },
+
+    #[error("failed to parse rustdoc JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("failed to render `{item}`: {message}")]
+    Render { item: String, message: String },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
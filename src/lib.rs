@@ -0,0 +1,84 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+mod error;
+mod repr;
+mod schema;
+mod segment;
+mod summary;
+#[cfg(test)]
+mod testing;
+mod utils;
+
+use serde::{Deserialize, Serialize};
+
+pub use error::ExtractError;
+pub use repr::{LinkVersion, OutputFormat, RenderOptions};
+pub use segment::{split_output_path, ItemMetadata, SegmentCollections};
+pub use summary::Summary;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Config {
+    pub manifest_path: Option<String>,
+    pub output_path: String,
+    // Kind of items to extract for packages without `kind`
+    pub default_kind: Option<String>,
+    // Lint level cap for building rustdoc JSON, defaults to `warn`
+    pub cap_lints: Option<String>,
+    // Toolchain building rustdoc JSON, e.g. `nightly-2024-01-01`, defaults to `nightly`
+    pub toolchain: Option<String>,
+    // Directory to cache the rustdoc JSON in, rebuilding only packages with modified sources
+    pub cache_dir: Option<String>,
+    // Table of contents to generate along with the pages
+    pub summary: Option<Summary>,
+    pub packages: Vec<Package>,
+    #[serde(flatten)]
+    pub options: RenderOptions,
+    // Fully qualified paths of the items to extract, in place of those selected by the packages
+    #[serde(default)]
+    pub items: Vec<String>,
+    // Format version the rustdoc JSON must have, any version is read if not specified
+    pub require_format_version: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Package {
+    pub name: String,
+    pub module_path: Option<String>,
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub follow_reexports: bool,
+    #[serde(default)]
+    pub expand_glob_reexports: bool,
+    // Pairs of kind and module path to extract, in place of those of the package
+    #[serde(default)]
+    pub selectors: Vec<Selector>,
+    // Features to build the package with, all of them unless any of these are given
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub no_default_features: bool,
+    pub all_features: Option<bool>,
+    // Rustdoc JSON built beforehand, read in place of building the package
+    pub json_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Selector {
+    pub kind: Option<String>,
+    pub module_path: Option<String>,
+}
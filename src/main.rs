@@ -15,43 +15,479 @@
 // specific language governing permissions and limitations
 // under the License.
 
-mod repr;
-mod segment;
-mod utils;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
 
-use std::fs::read_to_string;
+use anyhow::{bail, Error};
+use clap::{ArgEnum, Parser};
+use notify::{Event, RecursiveMode, Watcher};
+use termcolor::{ColorChoice, ColorSpec, StandardStream, WriteColor};
 
-use anyhow::Error;
-use clap::Parser;
-
-use segment::SegmentCollections;
-use serde::{Deserialize, Serialize};
+use comment_extract::{split_output_path, Config, ExtractError, OutputFormat, SegmentCollections};
 
 #[derive(Debug, Parser, PartialEq)]
 #[clap(author, version, about, long_about= None)]
 struct Args {
     #[clap(long, default_value = "rustdoc-extract.toml")]
     config: String,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct Config {
+    #[clap(long, help = "Do not fail when no items are collected")]
+    allow_empty: bool,
+    #[clap(long, help = "Regenerate the output when the crate sources change")]
+    watch: bool,
+    #[clap(long, arg_enum, default_value = "auto")]
+    color: Color,
+    #[clap(
+        long,
+        help = "Write the counts of unsupported constructs as JSON to the file"
+    )]
+    render_report: Option<String>,
+    #[clap(
+        long,
+        help = "Write the names and versions of the external crates linked from the pages as JSON to the file"
+    )]
+    external_crates: Option<String>,
+    #[clap(
+        long = "item",
+        help = "Extract only the item of the fully qualified path, may be repeated"
+    )]
+    items: Vec<String>,
+    #[clap(
+        long,
+        help = "Fail unless the rustdoc JSON has exactly this format version"
+    )]
+    require_format_version: Option<u32>,
+    #[clap(
+        long,
+        help = "Print the collected items as JSON instead of extracting them"
+    )]
+    list_items: bool,
+    #[clap(long, arg_enum, help = "Override `output_format` of the config")]
+    output_format: Option<OutputFormat>,
+    #[clap(
+        long,
+        help = "Apply the settings of the named table under `profiles` in the config"
+    )]
+    profile: Option<String>,
+    #[clap(long, help = "Override `manifest_path` of the config")]
     manifest_path: Option<String>,
-    output_path: String,
-    packages: Vec<Package>,
+    #[clap(
+        long,
+        help = "Fail if relative links of the written pages point to missing files"
+    )]
+    check_links: bool,
+    #[clap(
+        long,
+        requires = "items",
+        help = "Print the pages of the `--item` paths instead of writing them"
+    )]
+    stdout: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct Package {
-    name: String,
-    module_path: Option<String>,
-    kind: String,
+#[derive(Debug, Clone, Copy, PartialEq, ArgEnum)]
+enum Color {
+    Auto,
+    Always,
+    Never,
 }
 
-fn main() -> Result<(), Error> {
+// Changes arriving within this duration are handled by a single regeneration
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+fn main() -> ExitCode {
     let args = Args::parse();
-    let config: Config = toml::from_str(&read_to_string(args.config)?)?;
-    let collections: SegmentCollections = config.try_into()?;
 
-    collections.extract()
+    let result = match args.watch {
+        // A failing first run is reported like the later ones, as the sources may be fixed
+        true => {
+            if let Err(err) = run(&args) {
+                report(&err, args.color).expect("failed to write error to stderr");
+            }
+            watch(&args)
+        }
+        false => run(&args),
+    };
+
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            report(&err, args.color).expect("failed to write error to stderr");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), Error> {
+    let mut config = load_config(args)?;
+    if !args.items.is_empty() {
+        config.items = args.items.clone();
+    }
+    if let Some(version) = args.require_format_version {
+        config.require_format_version = Some(version);
+    }
+    if let Some(output_format) = args.output_format {
+        config.options.output_format = output_format;
+    }
+    let collections = SegmentCollections::from_config(config)?;
+
+    if collections.is_empty() && !args.allow_empty {
+        bail!(
+            "No items collected from the packages in `{}`, use `--allow-empty` to proceed anyway",
+            args.config
+        );
+    }
+
+    if args.list_items {
+        println!("{}", serde_json::to_string_pretty(&collections.items())?);
+        return Ok(());
+    }
+
+    if args.stdout {
+        for path in &args.items {
+            println!("{}", collections.render(path)?);
+        }
+        return Ok(());
+    }
+
+    collections.extract()?;
+
+    if args.check_links {
+        let broken = collections.check_links()?;
+        for (page, dest) in &broken {
+            eprintln!("warning: broken link `{}` in `{}`", dest, page.display());
+        }
+        if !broken.is_empty() {
+            bail!("{} broken links in the written pages", broken.len());
+        }
+    }
+
+    if let Some(path) = &args.render_report {
+        write(
+            path,
+            serde_json::to_string_pretty(&collections.unsupported_constructs())?,
+        )?;
+    }
+
+    if let Some(path) = &args.external_crates {
+        write(
+            path,
+            serde_json::to_string_pretty(&collections.external_crates())?,
+        )?;
+    }
+
+    Ok(())
+}
+
+// Read the config, with the keys of the selected profile and the command line taking the place
+// of the top-level ones
+fn load_config(args: &Args) -> Result<Config, Error> {
+    let mut table: toml::Table = toml::from_str(&read_to_string(&args.config)?)?;
+    let profiles = table.remove("profiles");
+
+    if let Some(name) = &args.profile {
+        let profile = match profiles.as_ref().and_then(|profiles| profiles.get(name)) {
+            Some(toml::Value::Table(profile)) => profile,
+            _ => bail!("No profile `{}` in `{}`", name, args.config),
+        };
+        table.extend(profile.clone());
+    }
+
+    let mut config: Config = table.try_into()?;
+    if let Some(manifest_path) = &args.manifest_path {
+        config.manifest_path = Some(manifest_path.clone());
+    }
+    Ok(config)
+}
+
+fn watch(args: &Args) -> Result<(), Error> {
+    let config = load_config(args)?;
+    let manifest_path = Path::new(config.manifest_path.as_deref().unwrap_or("Cargo.toml"));
+    let source_root = match manifest_path.parent() {
+        Some(path) if path != Path::new("") => path.canonicalize()?,
+        _ => Path::new(".").canonicalize()?,
+    };
+    // Changes made by cargo and by the extraction itself should not trigger a regeneration
+    let (output_root, _) = split_output_path(&config.output_path);
+    create_dir_all(&output_root)?;
+    let mut ignored = vec![source_root.join("target"), output_root.canonicalize()?];
+    if let Some(cache_dir) = &config.cache_dir {
+        create_dir_all(cache_dir)?;
+        ignored.push(Path::new(cache_dir).canonicalize()?);
+    }
+    for path in [&args.render_report, &args.external_crates]
+        .into_iter()
+        .flatten()
+    {
+        ignored.push(written_path(Path::new(path))?);
+    }
+
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&source_root, RecursiveMode::Recursive)?;
+
+    watch_changes(&rx, &ignored, WATCH_DEBOUNCE, || {
+        if let Err(err) = run(args) {
+            report(&err, args.color).expect("failed to write error to stderr");
+        }
+    })
+}
+
+// Call `regenerate` once for each burst of changes outside the `ignored` paths, until the
+// watcher stops sending events
+fn watch_changes(
+    rx: &Receiver<notify::Result<Event>>,
+    ignored: &[PathBuf],
+    debounce: Duration,
+    mut regenerate: impl FnMut(),
+) -> Result<(), Error> {
+    while let Ok(event) = rx.recv() {
+        let event = event?;
+        if event.kind.is_access()
+            || (event.paths.iter()).all(|path| ignored.iter().any(|dir| path.starts_with(dir)))
+        {
+            continue;
+        }
+
+        while rx.recv_timeout(debounce).is_ok() {}
+
+        regenerate();
+    }
+    Ok(())
+}
+
+// Canonical form of a path written by the extraction, which may not exist yet
+fn written_path(path: &Path) -> io::Result<PathBuf> {
+    let parent = match path.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+    Ok(parent
+        .canonicalize()?
+        .join(path.file_name().unwrap_or_default()))
+}
+
+// Print the error chain to stderr
+fn report(err: &Error, color: Color) -> io::Result<()> {
+    write_report(&mut StandardStream::stderr(color_choice(color)), err)
+}
+
+fn color_choice(color: Color) -> ColorChoice {
+    match color {
+        Color::Always => ColorChoice::Always,
+        Color::Never => ColorChoice::Never,
+        Color::Auto if io::stderr().is_terminal() => ColorChoice::Auto,
+        Color::Auto => ColorChoice::Never,
+    }
+}
+
+// Write the error chain, with the failing package, item and construct on their own lines
+fn write_report(stderr: &mut impl WriteColor, err: &Error) -> io::Result<()> {
+    stderr.set_color(
+        ColorSpec::new()
+            .set_fg(Some(termcolor::Color::Red))
+            .set_bold(true),
+    )?;
+    write!(stderr, "error")?;
+    stderr.reset()?;
+    writeln!(stderr, ": {}", err)?;
+
+    let details = match err.downcast_ref::<ExtractError>() {
+        Some(ExtractError::Build { package, .. }) => vec![("package", package.as_str())],
+        Some(ExtractError::Render { item, message }) => {
+            vec![("item", item.as_str()), ("reason", message.as_str())]
+        }
+        _ => vec![],
+    };
+    for (label, value) in details {
+        stderr.set_color(
+            ColorSpec::new()
+                .set_fg(Some(termcolor::Color::Cyan))
+                .set_bold(true),
+        )?;
+        write!(stderr, "  {:>9}", label)?;
+        stderr.reset()?;
+        writeln!(stderr, ": {}", value)?;
+    }
+
+    for cause in err.chain().skip(1) {
+        stderr.set_color(
+            ColorSpec::new()
+                .set_fg(Some(termcolor::Color::Yellow))
+                .set_bold(true),
+        )?;
+        write!(stderr, "  caused by")?;
+        stderr.reset()?;
+        writeln!(stderr, ": {}", cause)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{create_dir_all, remove_dir_all};
+    use std::path::{Path, PathBuf};
+
+    use notify::event::{EventKind, ModifyKind};
+    use termcolor::BufferWriter;
+
+    use super::*;
+
+    // Fresh directory for the files of a test
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = temp_dir().join("comment-extract-main-tests").join(name);
+        let _ = remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn written_paths_may_not_exist_yet() {
+        let dir = test_dir("written_paths");
+        let path = written_path(&dir.join("report.json")).unwrap();
+        assert_eq!(path, dir.canonicalize().unwrap().join("report.json"));
+        assert_eq!(
+            written_path(Path::new("report.json")).unwrap(),
+            Path::new(".").canonicalize().unwrap().join("report.json")
+        );
+    }
+
+    #[test]
+    fn changes_are_regenerated_once_per_burst() {
+        let ignored = [PathBuf::from("/src/target")];
+        let change = |path: &str| {
+            Ok(Event::new(EventKind::Modify(ModifyKind::Any)).add_path(PathBuf::from(path)))
+        };
+        let regenerations = |events: Vec<notify::Result<Event>>| {
+            let (tx, rx) = channel();
+            for event in events {
+                tx.send(event).unwrap();
+            }
+            drop(tx);
+            let mut count = 0;
+            watch_changes(&rx, &ignored, Duration::from_millis(10), || count += 1).unwrap();
+            count
+        };
+
+        assert_eq!(regenerations(vec![change("/src/lib.rs")]), 1);
+        assert_eq!(
+            regenerations(vec![change("/src/lib.rs"), change("/src/main.rs")]),
+            1
+        );
+        assert_eq!(regenerations(vec![change("/src/target/debug/out")]), 0);
+    }
+
+    // Arguments reading `config` written to the test directory
+    fn args(dir: &Path, config: &str, extra: &[&str]) -> Args {
+        let path = dir.join("rustdoc-extract.toml");
+        write(&path, config).unwrap();
+        Args::parse_from(
+            ["comment-extract", "--config", path.to_str().unwrap()]
+                .iter()
+                .chain(extra),
+        )
+    }
+
+    const PROFILES: &str = r#"
+manifest_path = "crate/Cargo.toml"
+output_path = "docs/api"
+packages = []
+
+[profiles.sphinx]
+output_path = "sphinx/api"
+output_format = "sphinx"
+"#;
+
+    #[test]
+    fn profiles_override_top_level_keys() {
+        let dir = test_dir("profiles");
+
+        let config = load_config(&args(&dir, PROFILES, &[])).unwrap();
+        assert_eq!(config.output_path, "docs/api");
+        assert_eq!(config.options.output_format, OutputFormat::Markdown);
+
+        let config = load_config(&args(&dir, PROFILES, &["--profile", "sphinx"])).unwrap();
+        assert_eq!(config.output_path, "sphinx/api");
+        assert_eq!(config.options.output_format, OutputFormat::Sphinx);
+        assert_eq!(config.manifest_path.as_deref(), Some("crate/Cargo.toml"));
+
+        let err = load_config(&args(&dir, PROFILES, &["--profile", "html"])).unwrap_err();
+        assert!(err.to_string().starts_with("No profile `html` in `"));
+    }
+
+    #[test]
+    fn manifest_path_argument_takes_the_place_of_the_config() {
+        let dir = test_dir("manifest_path");
+        let config = load_config(&args(
+            &dir,
+            PROFILES,
+            &["--manifest-path", "other/Cargo.toml"],
+        ))
+        .unwrap();
+        assert_eq!(config.manifest_path.as_deref(), Some("other/Cargo.toml"));
+    }
+
+    #[test]
+    fn empty_collections_fail_unless_allowed() {
+        let dir = test_dir("empty_collections");
+        let config = format!(
+            "output_path = {:?}\npackages = []\n",
+            dir.join("out").to_str().unwrap()
+        );
+
+        let err = run(&args(&dir, &config, &[])).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("No items collected from the packages in `"));
+        run(&args(&dir, &config, &["--allow-empty"])).unwrap();
+    }
+
+    #[test]
+    fn reports_are_colored_unless_disabled() {
+        let err = Error::from(ExtractError::Render {
+            item: "fixture::Point".to_string(),
+            message: "item missing from the index of its crate".to_string(),
+        });
+        let report = |color| {
+            let mut buffer = BufferWriter::stderr(color_choice(color)).buffer();
+            write_report(&mut buffer, &err).unwrap();
+            String::from_utf8(buffer.into_inner()).unwrap()
+        };
+
+        assert_eq!(
+            report(Color::Never),
+            "error: failed to render `fixture::Point`: item missing from the index of its crate\n       item: fixture::Point\n     reason: item missing from the index of its crate\n"
+        );
+        assert!(report(Color::Always).contains('\x1b'));
+    }
+
+    #[test]
+    fn unsupported_constructs_are_counted_in_the_render_report() {
+        let dir = test_dir("render_report");
+        let config = format!(
+            "output_path = {:?}\nitems = [\"fixture::utils::apply\"]\n\n[[packages]]\nname = \"fixture\"\njson_path = {:?}\n",
+            dir.join("out").to_str().unwrap(),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/fixture.json"),
+        );
+        let report = dir.join("report.json");
+        run(&args(
+            &dir,
+            &config,
+            &["--render-report", report.to_str().unwrap()],
+        ))
+        .unwrap();
+
+        // `F: for<'a> Fn(&'a u8)` and `Box<dyn Fn(u8)>` of `apply`
+        assert_eq!(
+            read_to_string(report).unwrap(),
+            r#"{
+  "GenericArgs::Parenthesized": 1,
+  "Higher-Rank Trait Bounds": 1
+}"#
+        );
+    }
 }
@@ -15,31 +15,135 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use clap::ArgEnum;
 use rustdoc_types::{
-    GenericArg, GenericArgs, GenericBound, ItemEnum, ItemKind, Term, TraitBoundModifier, Type,
-    TypeBinding, TypeBindingKind,
+    Abi, FnDecl, GenericArg, GenericArgs, GenericBound, GenericParamDefKind, Generics, Header, Id,
+    ItemEnum, ItemKind, Term, TraitBoundModifier, Type, TypeBinding, TypeBindingKind, Variant,
+    VariantKind, WherePredicate,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
     segment::{CachedItem, ItemId},
-    utils::caption,
+    utils::{
+        caption, cfg_condition, escape_html, on_unimplemented_message, table_cell, variant_name,
+    },
 };
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize, ArgEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Markdown,
+    // Markdown embedded into HTML pages by Sphinx
+    Sphinx,
+    // Pages of the schema in `schema`
+    Json,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkVersion {
+    // Versions of the documented crates
+    #[default]
+    Pinned,
+    Latest,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    pub output_format: OutputFormat,
+    // Annotate the first use of each generic parameter in a signature with its bounds
+    pub annotate_generic_bounds: bool,
+    // External crates whose items are linked, all crates are linked if not specified
+    pub link_crates: Option<Vec<String>>,
+    // Keep lines hidden from rustdoc output with `#` in rust code blocks
+    pub keep_hidden_doctest_lines: bool,
+    // List trait implementations generated by `#[derive]`
+    pub include_derived_impls: bool,
+    // List auto trait and blanket implementations
+    pub include_synthetic_impls: bool,
+    // Render `Panics`, `Safety` and `Errors` sections as alerts or admonitions
+    pub section_alerts: bool,
+    // Render types bound to associated types with their fully qualified paths
+    pub full_binding_paths: bool,
+    // Version of the docs.rs pages to link to
+    pub link_version: LinkVersion,
+    // Types nested deeper are truncated to `...`, defaults to `DEFAULT_MAX_TYPE_DEPTH`
+    pub max_type_depth: Option<usize>,
+    // Render the signature block of functions, defaults to true
+    pub render_signatures: Option<bool>,
+    // Copy files referenced by relative links in docs into `assets` of the output root
+    pub copy_assets: bool,
+    // Write all pages into this file below the output root, linking items by anchors
+    pub single_file: Option<String>,
+    // URL of external items with `{base}`, `{path}`, `{kind}` and `{name}` placeholders, defaults
+    // to `DEFAULT_LINK_TEMPLATE` of the rustdoc pages
+    pub link_template: Option<String>,
+    // Escape HTML tags written in docs, defaults to true except for Sphinx which renders them
+    pub escape_raw_html: Option<bool>,
+    // Render `→ ()` for functions returning unit
+    pub show_unit_return: bool,
+    // Remove a leading `# Name` heading of docs duplicating the heading of the page
+    pub strip_leading_heading: bool,
+    // Write the pages of each package under a directory named after the package
+    pub prefix_with_package: bool,
+}
+
+pub const DEFAULT_LINK_TEMPLATE: &str = "{base}{path}/{kind}.{name}.html";
+
+const DEFAULT_MAX_TYPE_DEPTH: usize = 32;
+
 pub(crate) trait Repr {
     fn repr(&self, root: &CachedItem) -> String;
 }
 
 impl Repr for CachedItem {
     fn repr(&self, _root: &CachedItem) -> String {
-        match self.kind() {
-            ItemKind::Function => {
-                let name = self.name();
-                format!(
-                    r#"# {}
+        let page = page_repr(self);
+
+        // Pages of associated items lead with a link back to their parent
+        match self
+            .item_summary()
+            .is_none()
+            .then(|| self.parent())
+            .flatten()
+        {
+            Some(parent) => {
+                let name = match self.link_to(&parent) {
+                    Some(link) => format!("[{}]({})", parent.name(), link),
+                    None => parent.name().to_string(),
+                };
+                format!("{} › {}\n\n{}", name, self.name(), page)
+            }
+            None => page,
+        }
+    }
+}
+
+fn page_repr(root: &CachedItem) -> String {
+    match root.kind() {
+        ItemKind::Function if !root.pool.options.render_signatures.unwrap_or(true) => {
+            format!("# {}\n\n{}\n", root.name(), root.docs())
+        }
+
+        ItemKind::Function => {
+            let name = root.name();
+            // Qualifiers, e.g. `unsafe extern "C"` of functions declared in extern blocks
+            let header = match &root.item().unwrap().inner {
+                ItemEnum::Function(func) => header_repr(&func.header),
+                _ => "".to_string(),
+            };
+            format!(
+                r#"# {}
 
 <dl>
     <dt class="sig">
-    <span class="sig-name">
+    {}<span class="sig-name">
         <span class="pre">{}</span>
     </span>
     {}
@@ -48,41 +152,330 @@ impl Repr for CachedItem {
 
 {}
 "#,
-                    name,
-                    name,
-                    self.item().unwrap().inner.repr(self),
-                    self.docs()
-                )
-            }
+                name,
+                if header.is_empty() {
+                    "".to_string()
+                } else {
+                    format!(
+                        r#"<em class="property">
+        <span class="pre">{}</span>
+    </em>
+    "#,
+                        escape_html(header.trim_end())
+                    )
+                },
+                name,
+                root.item().unwrap().inner.repr(root),
+                root.docs()
+            )
+        }
 
-            ItemKind::Struct => {
-                let methods = self
-                    .associated_methods()
-                    .into_iter()
-                    .map(|method| {
+        ItemKind::Struct | ItemKind::Enum | ItemKind::Union => {
+            let impl_methods = root.impl_methods();
+            // Methods of several impl blocks, or of a block with docs or a cfg condition, are
+            // listed under a heading of each block
+            let grouped = impl_methods.len() > 1
+                || (impl_methods.iter()).any(|(impl_item, _)| {
+                    let item = impl_item.item().unwrap();
+                    item.docs.as_deref().is_some_and(|docs| !docs.is_empty())
+                        || cfg_condition(&item.attrs).is_some()
+                });
+            let methods = match grouped {
+                false => method_rows(root, &root.associated_methods()),
+                true => "".to_string(),
+            };
+            let trait_impls = root
+                .trait_impls()
+                .into_iter()
+                .filter_map(|impl_| {
+                    impl_.trait_.as_ref().map(|trait_| {
                         format!(
-                            "| [{}]({}) | {} |",
-                            method.name(),
-                            self.cross_ref(&method),
-                            caption(method.item().unwrap())
+                            "- {}{}",
+                            if impl_.negative { "!" } else { "" },
+                            trait_.repr(root)
                         )
                     })
-                    .collect::<Vec<String>>()
-                    .join("\n");
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
 
-                if !methods.is_empty() {
+            let variants = root
+                .variants()
+                .into_iter()
+                .map(|variant| {
                     format!(
-                        "# {}\n\n{}\n\n# Methods\n| Method | Description |\n| --- | --- |\n{}",
-                        self.name(),
-                        self.docs(),
-                        methods
+                        "| {} | {} |",
+                        variant_repr(&variant, root),
+                        table_cell(caption(variant.item().unwrap()))
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+
+            let fields = root
+                .fields()
+                .into_iter()
+                .map(|field| {
+                    format!(
+                        "| {} | {} | {} |",
+                        field.name(),
+                        field.item().unwrap().inner.repr(root),
+                        table_cell(caption(field.item().unwrap()))
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+
+            let where_ = match &root.item().unwrap().inner {
+                ItemEnum::Struct(struct_) => where_clause(&struct_.generics.where_predicates, root),
+                ItemEnum::Enum(enum_) => where_clause(&enum_.generics.where_predicates, root),
+                ItemEnum::Union(union_) => where_clause(&union_.generics.where_predicates, root),
+                _ => None,
+            };
+            let mut sections = vec![format!(
+                "# {}\n\n{}{}",
+                root.name(),
+                where_
+                    .map(|where_| format!("{}\n\n", where_))
+                    .unwrap_or_default(),
+                root.docs()
+            )];
+            if !fields.is_empty() {
+                sections.push(format!(
+                    "# Fields\n| Field | Type | Description |\n| --- | --- | --- |\n{}",
+                    fields
+                ));
+            }
+            if !variants.is_empty() {
+                sections.push(format!(
+                    "# Variants\n| Variant | Description |\n| --- | --- |\n{}",
+                    variants
+                ));
+            }
+            if !methods.is_empty() {
+                sections.push(format!(
+                    "# Methods\n| Method | Description |\n| --- | --- |\n{}",
+                    methods
+                ));
+            } else if grouped {
+                sections.push(
+                    ["# Methods".to_string()]
+                        .into_iter()
+                        .chain(
+                            impl_methods
+                                .iter()
+                                .map(|(impl_item, methods)| impl_section(impl_item, methods, root)),
+                        )
+                        .collect::<Vec<String>>()
+                        .join("\n\n"),
+                );
+            }
+            if !trait_impls.is_empty() {
+                sections.push(format!("# Trait Implementations\n{}", trait_impls));
+            }
+            sections.join("\n\n")
+        }
+
+        ItemKind::Trait => {
+            let name = root.name();
+            let (is_unsafe, bounds, items, where_) = match &root.item().unwrap().inner {
+                ItemEnum::Trait(trait_) => (
+                    trait_.is_unsafe,
+                    trait_.bounds.as_slice(),
+                    trait_.items.as_slice(),
+                    where_clause(&trait_.generics.where_predicates, root)
+                        .map(|where_| format!("\n{}", where_))
+                        .unwrap_or_default(),
+                ),
+                _ => (false, &[][..], &[][..], "".to_string()),
+            };
+            let bounds = bounds
+                .iter()
+                .map(|bound| bound.repr(root))
+                .collect::<Vec<String>>()
+                .join(" + ");
+            let supertraits = if bounds.is_empty() {
+                "".to_string()
+            } else {
+                format!("**Supertraits:** {}\n\n", bounds)
+            };
+            let on_unimplemented = on_unimplemented_message(&root.item().unwrap().attrs)
+                .map(|message| format!("**Unimplemented message:** {}\n\n", escape_html(&message)))
+                .unwrap_or_default();
+
+            let (provided, required): (Vec<_>, Vec<_>) = (root.associated_methods().into_iter())
+                .partition(|method| {
+                    matches!(
+                        &method.item().unwrap().inner,
+                        ItemEnum::Function(func) if func.has_body
                     )
+                });
+            let items = (items.iter())
+                .map(|id| root.pool.clone().get(&ItemId::new(&root.id.pkg, id)))
+                .collect::<Vec<_>>();
+            let assoc_rows = |keyword: &str| {
+                (items.iter())
+                    .filter_map(|item| {
+                        let inner = &item.item()?.inner;
+                        let is_kind = match inner {
+                            ItemEnum::AssocType { .. } => keyword == "type",
+                            ItemEnum::AssocConst { .. } => keyword == "const",
+                            _ => false,
+                        };
+                        is_kind.then(|| {
+                            format!(
+                                "| {} {}{} | {} |",
+                                keyword,
+                                item.name(),
+                                inner.repr(root),
+                                table_cell(caption(item.item().unwrap()))
+                            )
+                        })
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            };
+
+            let mut sections = vec![format!(
+                r#"# {}
+
+<dl>
+    <dt class="sig">
+    <em class="property">
+        <span class="pre">{}trait</span>
+    </em>
+    <span class="sig-name">
+        <span class="pre">{}</span>
+    </span>
+    {}{}
+    </dt>
+</dl>
+
+{}{}{}"#,
+                name,
+                if is_unsafe { "unsafe " } else { "" },
+                name,
+                if bounds.is_empty() {
+                    "".to_string()
                 } else {
-                    format!("# {}\n\n{}", self.name(), self.docs())
+                    format!(": {}", bounds)
+                },
+                where_,
+                supertraits,
+                on_unimplemented,
+                root.docs()
+            )];
+            for (title, header, rows) in [
+                ("Required Methods", "Method", method_rows(root, &required)),
+                ("Provided Methods", "Method", method_rows(root, &provided)),
+                ("Associated Types", "Type", assoc_rows("type")),
+                ("Associated Constants", "Constant", assoc_rows("const")),
+            ] {
+                if !rows.is_empty() {
+                    sections.push(format!(
+                        "# {}\n| {} | Description |\n| --- | --- |\n{}",
+                        title, header, rows
+                    ));
                 }
             }
+            sections.join("\n\n") + "\n"
+        }
+
+        ItemKind::TypeAlias => {
+            let name = root.name();
+            format!(
+                r#"# {}
+
+<dl>
+    <dt class="sig">
+    <em class="property">
+        <span class="pre">type</span>
+    </em>
+    <span class="sig-name">
+        <span class="pre">{}</span>
+    </span>
+    {}
+    </dt>
+</dl>
+
+{}
+"#,
+                name,
+                name,
+                root.item().unwrap().inner.repr(root),
+                root.docs()
+            )
+        }
+
+        ItemKind::Constant | ItemKind::Static => {
+            let name = root.name();
+            let keyword = match &root.item().unwrap().inner {
+                // Statics declared in extern blocks have no initializer, and are written as in
+                // `unsafe extern` blocks
+                ItemEnum::Static(static_) if static_.expr.is_empty() && static_.mutable => {
+                    "unsafe static mut"
+                }
+                ItemEnum::Static(static_) if static_.expr.is_empty() => "unsafe static",
+                ItemEnum::Static(static_) if static_.mutable => "static mut",
+                ItemEnum::Static(_) => "static",
+                _ => "const",
+            };
+            format!(
+                r#"# {}
+
+<dl>
+    <dt class="sig">
+    <em class="property">
+        <span class="pre">{}</span>
+    </em>
+    <span class="sig-name">
+        <span class="pre">{}</span>
+    </span>
+    {}
+    </dt>
+</dl>
 
-            _ => unimplemented!("Unimplemented ItemKind: {:?}", self),
+{}
+"#,
+                name,
+                keyword,
+                name,
+                root.item().unwrap().inner.repr(root),
+                root.docs()
+            )
+        }
+
+        ItemKind::Module => {
+            let items = root
+                .module_items()
+                .into_iter()
+                .map(|item| {
+                    let name = match root.link_to(&item) {
+                        Some(link) => format!("[{}]({})", item.name(), link),
+                        None => item.name().to_string(),
+                    };
+                    format!(
+                        "| {} | {} |",
+                        name,
+                        table_cell(caption(item.item().unwrap()))
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+
+            let mut sections = vec![format!("# {}\n\n{}", root.name(), root.docs())];
+            if !items.is_empty() {
+                sections.push(format!(
+                    "# Items\n| Item | Description |\n| --- | --- |\n{}",
+                    items
+                ));
+            }
+            sections.join("\n\n")
+        }
+
+        kind => {
+            root.unsupported(format!("ItemKind::{:?}", kind));
+            format!("# {}\n\n{}", root.name(), root.docs())
         }
     }
 }
@@ -91,112 +484,294 @@ impl Repr for ItemEnum {
     fn repr(&self, root: &CachedItem) -> String {
         match self {
             ItemEnum::Function(func) => {
-                format!(
-                    r#"<span class="sig-paren">(</span>
-{}
-<span class="sig-paren">)</span>
-{}"#,
-                    func.decl
-                        .inputs
-                        .iter()
-                        .map(|(name, type_)| format!(
+                // Bounds annotated at the first use of the parameters are not repeated, and those
+                // of parameters used nowhere in the inputs or output stay in the generics
+                let annotate = root.pool.options.annotate_generic_bounds;
+                if annotate {
+                    *root.generic_bounds.borrow_mut() = generic_bounds(&func.generics, root);
+                }
+                let inputs = func
+                    .decl
+                    .inputs
+                    .iter()
+                    .map(|(name, type_)| match receiver_repr(name, type_) {
+                        Some(receiver) => format!(
+                            r#"<em class="sig-param n">
+    <span class="pre">{}</span>
+</em>"#,
+                            receiver
+                        ),
+                        None => format!(
                             r#"<em class="sig-param n">
     <span class="pre">{}</span>: <span class="pre">{}</span>
 </em>"#,
-                            name,
+                            // Destructured parameters are named by their pattern, which may be
+                            // missing for unnamed ones
+                            if name.is_empty() {
+                                "_".to_string()
+                            } else {
+                                escape_html(name)
+                            },
                             type_.repr(root)
-                        ))
-                        .collect::<Vec<String>>()
-                        .join(", "),
-                    func.decl
-                        .output
-                        .as_ref()
-                        .map(|type_| format!(" → {}", type_.repr(root)))
-                        .unwrap_or("".to_string())
+                        ),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                let output = output_repr(&func.decl, root);
+
+                format!(
+                    r#"{}<span class="sig-paren">(</span>
+{}
+<span class="sig-paren">)</span>
+{}{}"#,
+                    generic_params(&func.generics, root, !annotate),
+                    inputs,
+                    output,
+                    where_clause(
+                        (func.generics.where_predicates.iter()).filter(|predicate| {
+                            !(annotate
+                                && matches!(
+                                    predicate,
+                                    WherePredicate::BoundPredicate {
+                                        type_: Type::Generic(_),
+                                        ..
+                                    }
+                                ))
+                        }),
+                        root
+                    )
+                    .map(|where_| format!("\n{}", where_))
+                    .unwrap_or_default()
                 )
             }
-            _ => unimplemented!("Unimplemented ItemEnum: {:?}", self),
-        }
-    }
-}
+            ItemEnum::StructField(type_) => type_.repr(root),
 
-impl Repr for Type {
-    fn repr(&self, root: &CachedItem) -> String {
-        match self {
-            Type::Primitive(p) => format!(
-                "<a href=\"https://doc.rust-lang.org/std/primitive.{}.html\">{}</a>",
-                p, p
+            ItemEnum::TypeAlias(alias) => format!(
+                "{} = {}",
+                generic_params(&alias.generics, root, true),
+                alias.type_.repr(root)
             ),
 
-            Type::ResolvedPath(path) => path.repr(root),
-
-            Type::DynTrait(dyn_trait) => format!(
-                "dyn {}",
-                dyn_trait
-                    .traits
-                    .iter()
-                    .map(|poly_trait| {
+            // Evaluated values differing from the expression are shown as rustdoc does, e.g.
+            // `= 1 << 10 // 1_024usize`
+            ItemEnum::Constant(constant) => format!(
+                ": {} = {}{}",
+                constant.type_.repr(root),
+                escape_html(&constant.expr),
+                match &constant.value {
+                    Some(value) if value != &constant.expr => {
                         format!(
-                            "{}{}",
-                            if !poly_trait.generic_params.is_empty() {
-                                unimplemented!("Unimplemented: Higher-Rank Trait Bounds")
-                            } else {
-                                ""
-                            },
-                            &poly_trait.trait_.repr(root)
+                            " <span class=\"sig-value\">// {}</span>",
+                            escape_html(value)
                         )
-                    })
-                    .chain(dyn_trait.lifetime.iter().map(|t| t.to_string()))
-                    .collect::<Vec<String>>()
-                    .join(" + ")
+                    }
+                    _ => "".to_string(),
+                }
             ),
 
-            Type::Generic(t) => t.clone(),
+            ItemEnum::Static(static_) if static_.expr.is_empty() => {
+                format!(": {}", static_.type_.repr(root))
+            }
+            ItemEnum::Static(static_) => format!(
+                ": {} = {}",
+                static_.type_.repr(root),
+                escape_html(&static_.expr)
+            ),
 
-            Type::BorrowedRef {
-                lifetime,
-                mutable,
-                type_,
-            } => {
+            ItemEnum::AssocType {
+                generics,
+                bounds,
+                default,
+            } => format!(
+                "{}{}{}{}",
+                generic_params(generics, root, true),
+                if bounds.is_empty() {
+                    "".to_string()
+                } else {
+                    format!(
+                        ": {}",
+                        bounds
+                            .iter()
+                            .map(|bound| bound.repr(root))
+                            .collect::<Vec<String>>()
+                            .join(" + ")
+                    )
+                },
+                // Kept on the same line, as associated types are listed in tables
+                where_clause(&generics.where_predicates, root)
+                    .map(|where_| format!(" {}", where_))
+                    .unwrap_or_default(),
+                default
+                    .as_ref()
+                    .map(|default| format!(" = {}", default.repr(root)))
+                    .unwrap_or("".to_string())
+            ),
+
+            ItemEnum::AssocConst { type_, default } => format!(
+                ": {}{}",
+                type_.repr(root),
+                default
+                    .as_ref()
+                    .map(|default| format!(" = {}", escape_html(default)))
+                    .unwrap_or("".to_string())
+            ),
+            _ => root.unsupported(format!("ItemEnum::{}", variant_name(self))),
+        }
+    }
+}
+
+impl Repr for Type {
+    fn repr(&self, root: &CachedItem) -> String {
+        let depth = root.type_depth.get();
+        let max_depth = (root.pool.options.max_type_depth).unwrap_or(DEFAULT_MAX_TYPE_DEPTH);
+        if depth >= max_depth {
+            return "...".to_string();
+        }
+
+        root.type_depth.set(depth + 1);
+        let repr = type_repr(self, root);
+        root.type_depth.set(depth);
+        repr
+    }
+}
+
+fn type_repr(type_: &Type, root: &CachedItem) -> String {
+    match type_ {
+        Type::Primitive(p) => {
+            if root.pool.links_to_crate("std") {
                 format!(
-                    "&{}{}{}",
-                    lifetime
-                        .as_ref()
-                        .map(|a| format!("{} ", a))
-                        .unwrap_or("".to_string()),
-                    if *mutable { "mut " } else { "" },
-                    type_.repr(root)
+                    "<a href=\"{}std/primitive.{}.html\">{}</a>",
+                    root.pool.sysroot_root_url(),
+                    p,
+                    p
                 )
+            } else {
+                p.clone()
             }
+        }
 
-            Type::Tuple(types) => format!(
-                "({})",
-                types
-                    .iter()
-                    .map(|type_| type_.repr(root))
-                    .collect::<Vec<String>>()
-                    .join(", ")
-            ),
+        Type::ResolvedPath(path) => path.repr(root),
 
-            Type::Slice(slice) => format!("&[{}]", slice.repr(root)),
+        Type::DynTrait(dyn_trait) => format!(
+            "dyn {}",
+            dyn_trait
+                .traits
+                .iter()
+                .map(|poly_trait| {
+                    format!(
+                        "{}{}",
+                        if !poly_trait.generic_params.is_empty() {
+                            root.unsupported("Higher-Rank Trait Bounds".to_string())
+                        } else {
+                            "".to_string()
+                        },
+                        poly_trait.trait_.repr(root)
+                    )
+                })
+                .chain(dyn_trait.lifetime.iter().map(|t| t.to_string()))
+                .collect::<Vec<String>>()
+                .join(" + ")
+        ),
 
-            Type::Array { type_, len } => {
-                format!("[{}: {}]", type_.repr(root), len)
+        Type::Generic(t) => {
+            let bounds = root.generic_bounds.borrow_mut().remove(t);
+            match bounds {
+                Some(bounds) => format!("{}<span class=\"sig-bounds\">: {}</span>", t, bounds),
+                None => t.clone(),
             }
+        }
+
+        Type::BorrowedRef {
+            lifetime,
+            mutable,
+            type_,
+        } => {
+            format!(
+                "&{}{}{}",
+                lifetime
+                    .as_ref()
+                    .map(|a| format!("{} ", a))
+                    .unwrap_or("".to_string()),
+                if *mutable { "mut " } else { "" },
+                type_.repr(root)
+            )
+        }
+
+        Type::Tuple(types) => format!(
+            "({})",
+            types
+                .iter()
+                .map(|type_| type_.repr(root))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+
+        Type::Slice(slice) => format!("[{}]", slice.repr(root)),
 
-            Type::ImplTrait(bounds) => {
+        Type::QualifiedPath {
+            name,
+            args,
+            self_type,
+            trait_,
+        } => match trait_ {
+            Some(trait_) => format!(
+                "&lt;{} as {}&gt;::{}{}",
+                self_type.repr(root),
+                trait_.repr(root),
+                name,
+                args.repr(root)
+            ),
+            None => format!("{}::{}{}", self_type.repr(root), name, args.repr(root)),
+        },
+
+        Type::FunctionPointer(fn_pointer) => format!(
+            "{}{}fn({}){}",
+            if fn_pointer.generic_params.is_empty() {
+                "".to_string()
+            } else {
                 format!(
-                    "impl {}",
-                    bounds
-                        .iter()
-                        .map(|bound| bound.repr(root))
+                    "for&lt;{}&gt; ",
+                    (fn_pointer.generic_params.iter())
+                        .map(|param| param.name.clone())
                         .collect::<Vec<String>>()
-                        .join(" + ")
+                        .join(", ")
                 )
-            }
+            },
+            header_repr(&fn_pointer.header),
+            (fn_pointer.decl.inputs.iter())
+                .map(|(name, type_)| match name.as_str() {
+                    // Parameters of function pointers are usually unnamed
+                    "" | "_" => type_.repr(root),
+                    name => format!("{}: {}", escape_html(name), type_.repr(root)),
+                })
+                .chain(fn_pointer.decl.c_variadic.then(|| "...".to_string()))
+                .collect::<Vec<String>>()
+                .join(", "),
+            output_repr(&fn_pointer.decl, root)
+        ),
 
-            unknown => unimplemented!("Unimplemented Type: {:?}", unknown),
+        Type::RawPointer { mutable, type_ } => format!(
+            "*{} {}",
+            if *mutable { "mut" } else { "const" },
+            type_.repr(root)
+        ),
+
+        Type::Array { type_, len } => {
+            format!("[{}: {}]", type_.repr(root), len)
+        }
+
+        Type::ImplTrait(bounds) => {
+            format!(
+                "impl {}",
+                bounds
+                    .iter()
+                    .map(|bound| bound.repr(root))
+                    .collect::<Vec<String>>()
+                    .join(" + ")
+            )
         }
+
+        unknown => root.unsupported(format!("Type::{}", variant_name(unknown))),
     }
 }
 
@@ -205,18 +780,26 @@ impl Repr for TypeBinding {
         format!(
             "{}{}{}",
             self.name,
-            &self.args.repr(root),
+            self.args.repr(root),
             match &self.binding {
                 TypeBindingKind::Equality(term) => {
                     match term {
-                        Term::Type(type_) => type_.repr(root),
-                        Term::Constant(c) => {
-                            unimplemented!("Unimplemented TypeBindingKind: {:?}", c)
+                        Term::Type(Type::ResolvedPath(path))
+                            if root.pool.options.full_binding_paths =>
+                        {
+                            format!(" = {}", path_repr(path, root, true))
                         }
+                        Term::Type(type_) => format!(" = {}", type_.repr(root)),
+                        Term::Constant(constant) => format!(" = {}", escape_html(&constant.expr)),
                     }
                 }
-                TypeBindingKind::Constraint(c) =>
-                    unimplemented!("Unimplemented TypeBindingKing: {:?}", c),
+                TypeBindingKind::Constraint(bounds) => format!(
+                    ": {}",
+                    (bounds.iter())
+                        .map(|bound| bound.repr(root))
+                        .collect::<Vec<String>>()
+                        .join(" + ")
+                ),
             }
         )
     }
@@ -233,8 +816,8 @@ impl Repr for GenericArgs {
                             .map(|arg| match arg {
                                 GenericArg::Lifetime(a) => a.clone(),
                                 GenericArg::Type(type_) => type_.repr(root),
-                                unknown =>
-                                    unimplemented!("Unimplemented GenericArg: {:?}", unknown),
+                                GenericArg::Const(constant) => const_arg_repr(&constant.expr),
+                                GenericArg::Infer => "_".to_string(),
                             })
                             .chain(bindings.iter().map(|bind| bind.repr(root)))
                             .collect::<Vec<String>>()
@@ -244,25 +827,106 @@ impl Repr for GenericArgs {
                     "".to_string()
                 }
             }
-            _ => unimplemented!("Unimplemented GenericArgs: {:?}", self),
+            _ => root.unsupported(format!("GenericArgs::{}", variant_name(self))),
         }
     }
 }
 
 impl Repr for rustdoc_types::Path {
     fn repr(&self, root: &CachedItem) -> String {
-        let id = ItemId::new(&root.id.pkg, &self.id);
-        let item = root.pool.clone().get(&id);
+        path_repr(self, root, false)
+    }
+}
 
-        format!(
-            "<a href=\"{}\">{}</a>{}",
-            item.external_link(),
-            item.name(),
-            self.args
-                .as_deref()
-                .map(|args| args.repr(root))
-                .unwrap_or("".to_string())
-        )
+// Render a path named by its last segment, or by the full path when `qualified`.
+fn path_repr(path: &rustdoc_types::Path, root: &CachedItem, qualified: bool) -> String {
+    let id = ItemId::new(&root.id.pkg, &path.id);
+    let item = root.pool.clone().get(&id);
+    let link = root.link_to(&item);
+    let name = if item.is_missing() {
+        path.name.clone()
+    } else if qualified {
+        item.path().join("::")
+    } else {
+        item.name().to_string()
+    };
+    let args = path
+        .args
+        .as_deref()
+        .map(|args| explicit_args(args, &item).repr(root))
+        .unwrap_or("".to_string());
+
+    match link {
+        Some(link) => format!("<a href=\"{}\">{}</a>{}", link, name, args),
+        None => format!("{}{}", name, args),
+    }
+}
+
+// Const arg without the braces around a single path or literal, e.g. `MY_CONST` for
+// `{ MY_CONST }`, which are only needed for other expressions
+fn const_arg_repr(expr: &str) -> String {
+    let expr = expr.trim();
+    let inner = (expr.strip_prefix('{'))
+        .and_then(|expr| expr.strip_suffix('}'))
+        .map(|inner| inner.trim());
+    match inner {
+        Some(inner)
+            if !inner.is_empty()
+                && (inner.strip_prefix('-').unwrap_or(inner).chars())
+                    .all(|c| c.is_alphanumeric() || "_:.'".contains(c)) =>
+        {
+            escape_html(inner)
+        }
+        _ => escape_html(expr),
+    }
+}
+
+// Generic args without the trailing ones equal to the defaults of their parameters, as they are
+// usually omitted in the source
+fn explicit_args(args: &GenericArgs, item: &CachedItem) -> GenericArgs {
+    let generics = match item.item().map(|item| &item.inner) {
+        Some(ItemEnum::Struct(struct_)) => &struct_.generics,
+        Some(ItemEnum::Enum(enum_)) => &enum_.generics,
+        Some(ItemEnum::Union(union_)) => &union_.generics,
+        Some(ItemEnum::Trait(trait_)) => &trait_.generics,
+        Some(ItemEnum::TypeAlias(alias)) => &alias.generics,
+        _ => return args.clone(),
+    };
+    let GenericArgs::AngleBracketed { args, bindings } = args else {
+        return args.clone();
+    };
+
+    // Lifetimes have no defaults, and may be elided in the args
+    let params = (generics.params.iter())
+        .filter(|param| !matches!(param.kind, GenericParamDefKind::Lifetime { .. }))
+        .collect::<Vec<_>>();
+    let mut args = args.clone();
+    while let Some(arg) = args.last() {
+        let position = args
+            .iter()
+            .filter(|arg| !matches!(arg, GenericArg::Lifetime(_)))
+            .count();
+        let is_default = match (arg, position.checked_sub(1).and_then(|i| params.get(i))) {
+            (GenericArg::Type(type_), Some(param)) => matches!(
+                &param.kind,
+                GenericParamDefKind::Type { default: Some(default), .. } if default == type_
+            ),
+            (GenericArg::Const(constant), Some(param)) => matches!(
+                &param.kind,
+                GenericParamDefKind::Const { default: Some(default), .. }
+                    if default == &constant.expr
+            ),
+            _ => false,
+        };
+        if !is_default {
+            break;
+        }
+        args.pop();
+    }
+
+    GenericArgs::AngleBracketed {
+        args,
+        bindings: bindings.clone(),
     }
 }
 
@@ -275,16 +939,14 @@ impl Repr for GenericBound {
                 modifier,
             } => {
                 if !generic_params.is_empty() {
-                    unimplemented!("Unimplemented: Higher-Rank Trait Bounds")
+                    root.unsupported("Higher-Rank Trait Bounds".to_string())
                 } else {
                     format!(
                         "{}{}",
                         match modifier {
-                            TraitBoundModifier::None => "",
-                            TraitBoundModifier::Maybe => "?",
-                            TraitBoundModifier::MaybeConst => {
-                                unimplemented!("Unimplemented TraitBoundModifier: {:?}", self)
-                            }
+                            TraitBoundModifier::None => "".to_string(),
+                            TraitBoundModifier::Maybe => "?".to_string(),
+                            TraitBoundModifier::MaybeConst => "~const ".to_string(),
                         },
                         path.repr(root)
                     )
@@ -294,3 +956,838 @@ impl Repr for GenericBound {
         }
     }
 }
+
+impl Repr for WherePredicate {
+    fn repr(&self, root: &CachedItem) -> String {
+        let bounds = |bounds: &[GenericBound]| {
+            bounds
+                .iter()
+                .map(|bound| bound.repr(root))
+                .collect::<Vec<String>>()
+                .join(" + ")
+        };
+
+        match self {
+            WherePredicate::BoundPredicate {
+                type_,
+                bounds: type_bounds,
+                generic_params,
+            } => format!(
+                "{}{}: {}",
+                if generic_params.is_empty() {
+                    "".to_string()
+                } else {
+                    format!(
+                        "for&lt;{}&gt; ",
+                        (generic_params.iter())
+                            .map(|param| param.name.clone())
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    )
+                },
+                type_.repr(root),
+                bounds(type_bounds)
+            ),
+            WherePredicate::RegionPredicate {
+                lifetime,
+                bounds: lifetime_bounds,
+            } => format!("{}: {}", lifetime, bounds(lifetime_bounds)),
+            WherePredicate::EqPredicate { lhs, rhs } => format!(
+                "{} = {}",
+                lhs.repr(root),
+                match rhs {
+                    Term::Type(type_) => type_.repr(root),
+                    Term::Constant(constant) => escape_html(&constant.expr),
+                }
+            ),
+        }
+    }
+}
+
+// Rows of a table of methods linking to their pages
+fn method_rows(root: &CachedItem, methods: &[Rc<CachedItem>]) -> String {
+    methods
+        .iter()
+        .map(|method| {
+            format!(
+                "| [{}]({}) | {} |",
+                method.name(),
+                root.cross_ref(method),
+                table_cell(caption(method.item().unwrap()))
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Methods of an impl block under a heading of the block, e.g. `impl<T> Foo<T>`, followed by its
+// cfg condition and docs
+fn impl_section(impl_item: &CachedItem, methods: &[Rc<CachedItem>], root: &CachedItem) -> String {
+    let item = impl_item.item().unwrap();
+    let heading = match &item.inner {
+        ItemEnum::Impl(impl_) => format!(
+            "impl{} {}",
+            generic_params(&impl_.generics, root, true),
+            impl_.for_.repr(root)
+        ),
+        _ => "impl".to_string(),
+    };
+    let cfg = cfg_condition(&item.attrs)
+        .map(|cfg| format!("*Available on `{}` only.*\n\n", cfg))
+        .unwrap_or_default();
+    let docs = match impl_item.docs_on(root) {
+        docs if docs.is_empty() => docs,
+        docs => format!("{}\n\n", docs),
+    };
+    format!(
+        "## {}\n\n{}{}| Method | Description |\n| --- | --- |\n{}",
+        heading,
+        cfg,
+        docs,
+        method_rows(root, methods)
+    )
+}
+
+// Return type of a function or function pointer, e.g. ` → bool`, or empty for unit returns unless
+// `show_unit_return` is set
+fn output_repr(decl: &FnDecl, root: &CachedItem) -> String {
+    decl.output
+        .as_ref()
+        .map(|type_| format!(" → {}", type_.repr(root)))
+        .unwrap_or(match root.pool.options.show_unit_return {
+            true => " → ()".to_string(),
+            false => "".to_string(),
+        })
+}
+
+// Qualifiers of a function or function pointer, e.g. `unsafe extern "C" `
+fn header_repr(header: &Header) -> String {
+    let abi = match &header.abi {
+        Abi::Rust => None,
+        Abi::C { unwind } => Some(("C", *unwind)),
+        Abi::Cdecl { unwind } => Some(("cdecl", *unwind)),
+        Abi::Stdcall { unwind } => Some(("stdcall", *unwind)),
+        Abi::Fastcall { unwind } => Some(("fastcall", *unwind)),
+        Abi::Aapcs { unwind } => Some(("aapcs", *unwind)),
+        Abi::Win64 { unwind } => Some(("win64", *unwind)),
+        Abi::SysV64 { unwind } => Some(("sysv64", *unwind)),
+        Abi::System { unwind } => Some(("system", *unwind)),
+        Abi::Other(abi) => Some((abi.as_str(), false)),
+    };
+
+    format!(
+        "{}{}{}{}",
+        if header.const_ { "const " } else { "" },
+        if header.async_ { "async " } else { "" },
+        if header.unsafe_ { "unsafe " } else { "" },
+        abi.map(|(abi, unwind)| format!(
+            "extern \"{}{}\" ",
+            abi,
+            if unwind { "-unwind" } else { "" }
+        ))
+        .unwrap_or_default()
+    )
+}
+
+// Variant with its fields inline, e.g. `Some(T)` or `Point { x: f64, y: f64 }`
+fn variant_repr(variant: &CachedItem, root: &CachedItem) -> String {
+    let field = |id: &Id| root.pool.clone().get(&ItemId::new(&root.id.pkg, id));
+    match &variant.item().unwrap().inner {
+        ItemEnum::Variant(Variant {
+            kind: VariantKind::Tuple(fields),
+            ..
+        }) => format!(
+            "{}({})",
+            variant.name(),
+            (fields.iter())
+                .map(|id| match id {
+                    Some(id) => field(id).item().unwrap().inner.repr(root),
+                    // Stripped fields, e.g. private ones
+                    None => "_".to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        ItemEnum::Variant(Variant {
+            kind: VariantKind::Struct { fields, .. },
+            ..
+        }) => format!(
+            "{} {{ {} }}",
+            variant.name(),
+            (fields.iter())
+                .map(|id| {
+                    let field = field(id);
+                    format!(
+                        "{}: {}",
+                        field.name(),
+                        field.item().unwrap().inner.repr(root)
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        _ => variant.name().to_string(),
+    }
+}
+
+// Shorthand of `self`, `&self` and `&mut self` receivers. Arbitrary self types, e.g.
+// `self: Pin<&mut Self>`, are rendered as other parameters.
+fn receiver_repr(name: &str, type_: &Type) -> Option<String> {
+    let is_self = |type_: &Type| matches!(type_, Type::Generic(t) if t == "Self");
+    match type_ {
+        _ if name != "self" => None,
+        type_ if is_self(type_) => Some("self".to_string()),
+        Type::BorrowedRef {
+            lifetime,
+            mutable,
+            type_,
+        } if is_self(type_) => Some(format!(
+            "&{}{}self",
+            lifetime
+                .as_ref()
+                .map(|a| format!("{} ", a))
+                .unwrap_or("".to_string()),
+            if *mutable { "mut " } else { "" }
+        )),
+        _ => None,
+    }
+}
+
+// Generic parameters with their bounds and defaults, e.g. `<'a, T: Clone = u8, const N: usize>`,
+// or empty if there are none. Without `with_bounds`, type parameters only keep the bounds not
+// yet annotated at their uses in the signature.
+fn generic_params(generics: &Generics, root: &CachedItem, with_bounds: bool) -> String {
+    let bounds = |name: &str, bounds: &[GenericBound]| {
+        if !with_bounds {
+            (root.generic_bounds.borrow_mut().remove(name))
+                .map(|bounds| format!(": {}", bounds))
+                .unwrap_or("".to_string())
+        } else if bounds.is_empty() {
+            "".to_string()
+        } else {
+            format!(
+                ": {}",
+                bounds
+                    .iter()
+                    .map(|bound| bound.repr(root))
+                    .collect::<Vec<String>>()
+                    .join(" + ")
+            )
+        }
+    };
+    let params = (generics.params.iter())
+        .filter_map(|param| match &param.kind {
+            GenericParamDefKind::Lifetime { outlives } => Some(if outlives.is_empty() {
+                param.name.clone()
+            } else {
+                format!("{}: {}", param.name, outlives.join(" + "))
+            }),
+            // Parameters of `impl Trait` arguments
+            GenericParamDefKind::Type {
+                synthetic: true, ..
+            } => None,
+            GenericParamDefKind::Type {
+                bounds: param_bounds,
+                default,
+                ..
+            } => Some(format!(
+                "{}{}{}",
+                param.name,
+                bounds(&param.name, param_bounds),
+                default
+                    .as_ref()
+                    .map(|default| format!(" = {}", default.repr(root)))
+                    .unwrap_or("".to_string())
+            )),
+            GenericParamDefKind::Const { type_, default } => Some(format!(
+                "const {}: {}{}",
+                param.name,
+                type_.repr(root),
+                default
+                    .as_ref()
+                    .map(|default| format!(" = {}", escape_html(default)))
+                    .unwrap_or("".to_string())
+            )),
+        })
+        .collect::<Vec<String>>();
+
+    if params.is_empty() {
+        "".to_string()
+    } else {
+        format!("&lt;{}&gt;", params.join(", "))
+    }
+}
+
+// `where` clause of a signature or type, or `None` if there are no predicates
+fn where_clause<'a>(
+    predicates: impl IntoIterator<Item = &'a WherePredicate>,
+    root: &CachedItem,
+) -> Option<String> {
+    let predicates = (predicates.into_iter())
+        .map(|predicate| predicate.repr(root))
+        .collect::<Vec<String>>();
+    (!predicates.is_empty()).then(|| {
+        format!(
+            r#"<span class="sig-where">where {}</span>"#,
+            predicates.join(", ")
+        )
+    })
+}
+
+// Collect bounds of generic type parameters declared in `generics`, including those
+// expressed in the where-clause, keyed by parameter name.
+fn generic_bounds(generics: &Generics, root: &CachedItem) -> HashMap<String, String> {
+    let mut bounds: HashMap<String, Vec<String>> = HashMap::new();
+
+    for param in &generics.params {
+        if let GenericParamDefKind::Type {
+            bounds: param_bounds,
+            synthetic: false,
+            ..
+        } = &param.kind
+        {
+            bounds
+                .entry(param.name.clone())
+                .or_default()
+                .extend(param_bounds.iter().map(|bound| bound.repr(root)));
+        }
+    }
+
+    for predicate in &generics.where_predicates {
+        if let WherePredicate::BoundPredicate {
+            type_: Type::Generic(name),
+            bounds: pred_bounds,
+            ..
+        } = predicate
+        {
+            bounds
+                .entry(name.clone())
+                .or_default()
+                .extend(pred_bounds.iter().map(|bound| bound.repr(root)));
+        }
+    }
+
+    bounds
+        .into_iter()
+        .filter(|(_, bounds)| !bounds.is_empty())
+        .map(|(name, bounds)| (name, bounds.join(" + ")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use super::const_arg_repr;
+    use crate::testing::{collections, temp_dir};
+
+    const STRUCTS: &str = r#"
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+"#;
+
+    const FUNCTIONS: &str = r#"
+[[packages]]
+name = "fixture"
+kind = "function"
+json_path = "{fixture}"
+"#;
+
+    const TRAITS: &str = r#"
+[[packages]]
+name = "fixture"
+kind = "trait"
+json_path = "{fixture}"
+"#;
+
+    const ANNOTATED: &str = r#"
+annotate_generic_bounds = true
+
+[[packages]]
+name = "fixture"
+kind = "function"
+json_path = "{fixture}"
+"#;
+
+    // Page written for `file` below the output root when extracting the packages of `toml`
+    fn extracted_page(test: &str, toml: &str, file: &str) -> String {
+        let dir = temp_dir(test);
+        collections(&dir, toml).extract().unwrap();
+        read_to_string(dir.join("out").join(file)).unwrap()
+    }
+
+    #[test]
+    fn field_types_link_to_extracted_items() {
+        let collections = collections(&temp_dir("field_types_link"), STRUCTS);
+        let page = collections.render("fixture::shapes::Circle").unwrap();
+        assert!(
+            page.contains(r#"| center | <a href="Point.md">Point</a> | Center of the circle. |"#)
+        );
+    }
+
+    #[test]
+    fn methods_are_grouped_by_impl_block() {
+        let collections = collections(&temp_dir("methods_are_grouped_by_impl_block"), STRUCTS);
+        let page = collections.render("fixture::shapes::Wrapper").unwrap();
+        let u8 = r#"<a href="https://doc.rust-lang.org/nightly/std/primitive.u8.html">u8</a>"#;
+        let u16 = r#"<a href="https://doc.rust-lang.org/nightly/std/primitive.u16.html">u16</a>"#;
+        assert!(page.contains(&format!(
+            "# Methods\n\n## impl <a href=\"Wrapper.md\">Wrapper</a>&lt;{}&gt;\n\nByte wrappers, see [`Point`](Point.md).\n\n| Method | Description |\n| --- | --- |\n| [new](Wrapper/new.md) | Wraps a byte. |",
+            u8
+        )));
+        assert!(page.contains(&format!(
+            "## impl <a href=\"Wrapper.md\">Wrapper</a>&lt;{}&gt;\n\n*Available on `feature = \"wide\"` only.*\n\n| Method | Description |\n| --- | --- |\n| [new](Wrapper/new-2.md) | Wraps a wide value. |",
+            u16
+        )));
+    }
+
+    #[test]
+    fn single_impl_blocks_are_grouped_with_docs_or_cfg() {
+        let collections = collections(&temp_dir("single_impl_blocks_are_grouped"), STRUCTS);
+        let circle = collections.render("fixture::shapes::Circle").unwrap();
+        assert!(circle.contains(
+            "## impl <a href=\"Circle.md\">Circle</a>\n\n*Available on `feature = \"geometry\"` only.*\n\nMeasurements of circles.\n\n| Method |"
+        ));
+
+        let point = collections.render("fixture::shapes::Point").unwrap();
+        assert!(point.contains(
+            "# Methods\n| Method | Description |\n| --- | --- |\n| [new](Point/new.md) | Creates a point. |"
+        ));
+    }
+
+    #[test]
+    fn generic_bounds_are_annotated_at_first_use() {
+        let collections = collections(&temp_dir("generic_bounds_annotated"), ANNOTATED);
+        let page = collections.render("fixture::utils::cloned").unwrap();
+        assert!(page.contains("&lt;T&gt;<span class=\"sig-paren\">"));
+        assert!(page.contains(
+            r#"&T<span class="sig-bounds">: <a href="https://doc.rust-lang.org/nightly/std/clone/trait.Clone.html">Clone</a></span>"#
+        ));
+        assert!(page.contains(" → T\n"));
+    }
+
+    #[test]
+    fn bounds_of_unused_generic_params_are_kept() {
+        let collections = collections(&temp_dir("unused_generic_params"), ANNOTATED);
+        let page = collections.render("fixture::utils::type_name").unwrap();
+        assert!(page.contains(
+            r#"&lt;T: ?<a href="https://doc.rust-lang.org/nightly/std/marker/trait.Sized.html">Sized</a>&gt;"#
+        ));
+    }
+
+    #[test]
+    fn where_clauses_of_types_match_signatures() {
+        let collections = collections(&temp_dir("where_clauses_of_types"), STRUCTS);
+        let page = collections.render("fixture::shapes::Borrowed").unwrap();
+        assert!(page.starts_with(
+            "# Borrowed\n\n<span class=\"sig-where\">where T: 'a</span>\n\nA borrowed value."
+        ));
+    }
+
+    #[test]
+    fn braces_of_simple_const_args_are_dropped() {
+        assert_eq!(const_arg_repr("{ MY_CONST }"), "MY_CONST");
+        assert_eq!(const_arg_repr("{ consts::SIZE }"), "consts::SIZE");
+        assert_eq!(const_arg_repr("{-1}"), "-1");
+        assert_eq!(const_arg_repr("{ N + 1 }"), "{ N + 1 }");
+        assert_eq!(const_arg_repr("{ a < b }"), "{ a &lt; b }");
+        assert_eq!(const_arg_repr("16"), "16");
+    }
+
+    #[test]
+    fn projected_traits_are_linked() {
+        let collections = collections(&temp_dir("projected_traits"), FUNCTIONS);
+        let page = collections.render("fixture::utils::first").unwrap();
+        assert!(page.contains(
+            r#" → &lt;I as <a href="https://doc.rust-lang.org/nightly/std/iter/trait.Iterator.html">Iterator</a>&gt;::Item"#
+        ));
+    }
+
+    #[test]
+    fn impl_trait_bindings_are_linked() {
+        let collections = collections(&temp_dir("impl_trait_bindings"), FUNCTIONS);
+        let page = collections.render("fixture::utils::points").unwrap();
+        assert!(page.contains(
+            r#" → impl <a href="https://doc.rust-lang.org/nightly/std/iter/trait.Iterator.html">Iterator</a>&lt;Item = <a href="https://docs.rs/fixture/0.1.0/fixture/shapes/struct.Point.html">Point</a>&gt;"#
+        ));
+    }
+
+    #[test]
+    fn variant_payloads_are_linked() {
+        let collections = collections(
+            &temp_dir("variant_payloads"),
+            r#"
+[[packages]]
+name = "fixture"
+kind = "enum"
+json_path = "{fixture}"
+"#,
+        );
+        let page = collections.render("fixture::ShapeKind").unwrap();
+        assert!(page.contains(
+            r#"| Io(<a href="https://doc.rust-lang.org/nightly/std/io/struct.Error.html">Error</a>) | Shapes failed to load. |"#
+        ));
+        assert!(page.contains(
+            r#"| Custom(<a href="https://docs.rs/fixture/0.1.0/fixture/shapes/struct.Point.html">Point</a>) |"#
+        ));
+    }
+
+    #[test]
+    fn dyn_trait_bindings_are_rendered() {
+        let collections = collections(&temp_dir("dyn_trait_bindings"), FUNCTIONS);
+        let page = collections.render("fixture::utils::consume").unwrap();
+        assert!(page.contains(
+            r#"&dyn <a href="https://doc.rust-lang.org/nightly/std/iter/trait.Iterator.html">Iterator</a>&lt;Item = <a href="https://doc.rust-lang.org/nightly/std/primitive.u8.html">u8</a>&gt;"#
+        ));
+    }
+
+    #[test]
+    fn only_listed_external_crates_are_linked() {
+        let collections = collections(
+            &temp_dir("link_crates"),
+            &format!("link_crates = [\"serde\"]\n{}", STRUCTS),
+        );
+        let page = collections.render("fixture::shapes::Circle").unwrap();
+        assert!(page.contains("| label | String | Label of the circle. |"));
+        assert!(page.contains(
+            r#"| ignored | <a href="https://docs.rs/serde/1.0.197/serde/de/struct.IgnoredAny.html">IgnoredAny</a> |"#
+        ));
+    }
+
+    #[test]
+    fn associated_constants_show_their_defaults() {
+        let collections = collections(&temp_dir("associated_constant_defaults"), TRAITS);
+        let page = collections.render("fixture::Area").unwrap();
+        assert!(page.contains(
+            r#"| const SIDES: <a href="https://doc.rust-lang.org/nightly/std/primitive.usize.html">usize</a> = 4 | Number of sides. |"#
+        ));
+    }
+
+    #[test]
+    fn unsafe_traits_are_marked() {
+        let collections = collections(&temp_dir("unsafe_traits"), TRAITS);
+        let page = collections.render("fixture::RawShape").unwrap();
+        assert!(page.contains(r#"<span class="pre">unsafe trait</span>"#));
+        let page = collections.render("fixture::Area").unwrap();
+        assert!(page.contains(r#"<span class="pre">trait</span>"#));
+    }
+
+    #[test]
+    fn borrowed_slices_have_a_single_reference() {
+        let collections = collections(&temp_dir("borrowed_slices"), FUNCTIONS);
+        let page = collections.render("fixture::utils::checksum").unwrap();
+        let u8_ = r#"<a href="https://doc.rust-lang.org/nightly/std/primitive.u8.html">u8</a>"#;
+        for param in [
+            format!("data</span>: <span class=\"pre\">&[{}]</span>", u8_),
+            format!("out</span>: <span class=\"pre\">&mut [{}]</span>", u8_),
+            format!("tail</span>: <span class=\"pre\">&'a [{}]</span>", u8_),
+        ] {
+            assert!(page.contains(&param), "{}", param);
+        }
+    }
+
+    #[test]
+    fn nested_impl_trait_bindings() {
+        let collections = collections(&temp_dir("nested_impl_trait_bindings"), FUNCTIONS);
+        let page = collections.render("fixture::utils::labels").unwrap();
+        assert!(page.contains(
+            r#" → impl <a href="https://doc.rust-lang.org/nightly/std/iter/trait.Iterator.html">Iterator</a>&lt;Item = impl <a href="https://doc.rust-lang.org/nightly/std/fmt/trait.Display.html">Display</a>&gt;"#
+        ));
+    }
+
+    #[test]
+    fn destructured_and_unnamed_params() {
+        let collections = collections(&temp_dir("destructured_params"), FUNCTIONS);
+        let page = collections.render("fixture::utils::distance").unwrap();
+        assert!(page.contains(r#"<span class="pre">(x, y)</span>: <span class="pre">("#));
+        assert!(page.contains(r#"<span class="pre">_</span>: <span class="pre">"#));
+    }
+
+    #[test]
+    fn derived_impls_are_listed_only_if_included() {
+        let debug =
+            r#"- <a href="https://doc.rust-lang.org/nightly/std/fmt/trait.Debug.html">Debug</a>"#;
+        let display = r#"- <a href="https://doc.rust-lang.org/nightly/std/fmt/trait.Display.html">Display</a>"#;
+
+        let page = (collections(&temp_dir("derived_impls_excluded"), STRUCTS))
+            .render("fixture::shapes::Point")
+            .unwrap();
+        assert!(page.contains(display));
+        assert!(!page.contains(debug));
+
+        let page = collections(
+            &temp_dir("derived_impls_included"),
+            &format!("include_derived_impls = true\n{}", STRUCTS),
+        )
+        .render("fixture::shapes::Point")
+        .unwrap();
+        assert!(page.contains(debug));
+    }
+
+    #[test]
+    fn const_generic_args_are_rendered_unless_default() {
+        let collections = collections(&temp_dir("const_generic_args"), FUNCTIONS);
+        let page = collections.render("fixture::utils::buffers").unwrap();
+        let buffer =
+            r#"<a href="https://docs.rs/fixture/0.1.0/fixture/struct.Buffer.html">Buffer</a>"#;
+        assert!(page.contains(&format!(
+            "small</span>: <span class=\"pre\">{}</span>",
+            buffer
+        )));
+        assert!(page.contains(&format!(
+            "large</span>: <span class=\"pre\">{}&lt;32&gt;</span>",
+            buffer
+        )));
+    }
+
+    #[test]
+    fn binding_paths_are_short_unless_full() {
+        let point = r#"<a href="https://docs.rs/fixture/0.1.0/fixture/shapes/struct.Point.html">"#;
+
+        let page = (collections(&temp_dir("short_binding_paths"), FUNCTIONS))
+            .render("fixture::utils::points")
+            .unwrap();
+        assert!(page.contains(&format!("Item = {}Point</a>&gt;", point)));
+
+        let page = collections(
+            &temp_dir("full_binding_paths"),
+            &format!("full_binding_paths = true\n{}", FUNCTIONS),
+        )
+        .render("fixture::utils::points")
+        .unwrap();
+        assert!(page.contains(&format!("Item = {}fixture::shapes::Point</a>&gt;", point)));
+    }
+
+    #[test]
+    fn latest_docs_rs_pages_are_linked() {
+        let page = collections(
+            &temp_dir("latest_link_version"),
+            &format!("link_version = \"latest\"\n{}", STRUCTS),
+        )
+        .render("fixture::shapes::Circle")
+        .unwrap();
+        assert!(page.contains(
+            r#"<a href="https://docs.rs/serde/latest/serde/de/struct.IgnoredAny.html">"#
+        ));
+
+        let page = collections(
+            &temp_dir("latest_link_version_of_packages"),
+            &format!("link_version = \"latest\"\n{}", FUNCTIONS),
+        )
+        .render("fixture::utils::points")
+        .unwrap();
+        assert!(page.contains(
+            r#"<a href="https://docs.rs/fixture/latest/fixture/shapes/struct.Point.html">"#
+        ));
+    }
+
+    #[test]
+    fn supertraits_are_linked() {
+        let collections = collections(&temp_dir("supertraits"), TRAITS);
+        let page = collections.render("fixture::Shape").unwrap();
+        assert!(page.contains(
+            r#"**Supertraits:** <a href="https://doc.rust-lang.org/nightly/std/fmt/trait.Debug.html">Debug</a> + <a href="Area.md">Area</a>"#
+        ));
+    }
+
+    #[test]
+    fn unimplemented_messages_are_rendered() {
+        let collections = collections(&temp_dir("unimplemented_messages"), TRAITS);
+        let page = collections.render("fixture::Shape").unwrap();
+        assert!(page.contains("**Unimplemented message:** `{Self}` is not a shape\n\n"));
+        let page = collections.render("fixture::RawShape").unwrap();
+        assert!(!page.contains("**Unimplemented message:**"));
+    }
+
+    #[test]
+    fn deeply_nested_types_are_truncated() {
+        let option =
+            r#"<a href="https://doc.rust-lang.org/nightly/std/option/enum.Option.html">Option</a>"#;
+        let page = collections(
+            &temp_dir("deeply_nested_types"),
+            &format!("max_type_depth = 2\n{}", FUNCTIONS),
+        )
+        .render("fixture::utils::nested")
+        .unwrap();
+        assert!(page.contains(&format!(" → {0}&lt;{0}&lt;...&gt;&gt;\n", option)));
+    }
+
+    #[test]
+    fn external_links_have_the_kind_of_their_target() {
+        let collections = collections(&temp_dir("external_link_kinds"), FUNCTIONS);
+        let page = collections.render("fixture::utils::shape_of").unwrap();
+        assert!(page.contains(
+            r#"&dyn <a href="https://docs.rs/fixture/0.1.0/fixture/trait.Area.html">Area</a>"#
+        ));
+        assert!(page.contains(
+            r#"<a href="https://docs.rs/fixture/0.1.0/fixture/enum.ShapeKind.html">ShapeKind</a>"#
+        ));
+    }
+
+    #[test]
+    fn signatures_can_be_omitted() {
+        let page = collections(
+            &temp_dir("signatures_omitted"),
+            &format!("render_signatures = false\n{}", FUNCTIONS),
+        )
+        .render("fixture::utils::helper")
+        .unwrap();
+        assert_eq!(page, "# helper\n\nHelps.\n");
+    }
+
+    #[test]
+    fn default_generic_args_are_omitted() {
+        let collections = collections(&temp_dir("default_generic_args"), FUNCTIONS);
+        let page = collections.render("fixture::utils::lookup").unwrap();
+        assert!(page.contains(
+            r#"map</span>: <span class="pre">&<a href="https://docs.rs/fixture/0.1.0/fixture/struct.Map.html">Map</a>&lt;<a href="https://doc.rust-lang.org/nightly/std/string/struct.String.html">String</a>, <a href="https://doc.rust-lang.org/nightly/std/primitive.u32.html">u32</a>&gt;</span>"#
+        ));
+        assert!(!page.contains("DefaultHasher"));
+    }
+
+    #[test]
+    fn generic_args_of_external_types_are_kept_as_written() {
+        let collections = collections(&temp_dir("external_generic_args"), FUNCTIONS);
+        let page = collections.render("fixture::utils::tally").unwrap();
+        let hash_map = r#"<a href="https://doc.rust-lang.org/nightly/std/collections/hash_map/struct.HashMap.html">HashMap</a>"#;
+        let string = r#"<a href="https://doc.rust-lang.org/nightly/std/string/struct.String.html">String</a>"#;
+        let u32_ = r#"<a href="https://doc.rust-lang.org/nightly/std/primitive.u32.html">u32</a>"#;
+        let random_state = r#"<a href="https://doc.rust-lang.org/nightly/std/collections/hash_map/struct.RandomState.html">RandomState</a>"#;
+        // The default hasher of `HashMap` is unknown without its generics in the index
+        assert!(page.contains(&format!(
+            "words</span>: <span class=\"pre\">{}&lt;{}, {}&gt;</span>",
+            hash_map, string, u32_
+        )));
+        assert!(page.contains(&format!(
+            "seeded</span>: <span class=\"pre\">{}&lt;{}, {}, {}&gt;</span>",
+            hash_map, string, u32_, random_state
+        )));
+    }
+
+    #[test]
+    fn method_pages_lead_with_their_parent() {
+        let page = extracted_page(
+            "method_parent_links",
+            STRUCTS,
+            "fixture/shapes/Point/new.md",
+        );
+        assert!(page.starts_with("[Point](../Point.md) › new\n\n# new\n"));
+    }
+
+    #[test]
+    fn same_named_methods_have_pages_of_their_own() {
+        let dir = temp_dir("same_named_methods");
+        collections(&dir, STRUCTS).extract().unwrap();
+        let page = |file: &str| read_to_string(dir.join("out/fixture/shapes").join(file)).unwrap();
+
+        assert!(page("Wrapper/new.md").ends_with("Wraps a byte.\n"));
+        assert!(page("Wrapper/new-2.md").ends_with("Wraps a wide value.\n"));
+        let wrapper = page("Wrapper.md");
+        assert!(wrapper.contains("| [new](Wrapper/new.md) | Wraps a byte. |"));
+        assert!(wrapper.contains("| [new](Wrapper/new-2.md) | Wraps a wide value. |"));
+    }
+
+    #[test]
+    fn constant_types_are_linked() {
+        let collections = collections(
+            &temp_dir("constant_types_are_linked"),
+            r#"
+[[packages]]
+name = "fixture"
+kind = "constant"
+json_path = "{fixture}"
+"#,
+        );
+        let page = collections.render("fixture::ORIGIN").unwrap();
+        assert!(page.contains(
+            ": <a href=\"https://docs.rs/fixture/0.1.0/fixture/shapes/struct.Point.html\">Point</a> = Point { x: 0.0, y: 0.0 }"
+        ));
+    }
+
+    #[test]
+    fn arbitrary_self_types_are_rendered() {
+        let test = "arbitrary_self_types_are_rendered";
+        let poll = extracted_page(test, STRUCTS, "fixture/shapes/Circle/poll.md");
+        assert!(poll.contains(
+            "<span class=\"pre\">self</span>: <span class=\"pre\"><a href=\"https://doc.rust-lang.org/nightly/std/pin/struct.Pin.html\">Pin</a>&lt;&mut Self&gt;</span>"
+        ));
+        // Plain receivers keep their shorthand
+        let distance_to = extracted_page(test, STRUCTS, "fixture/shapes/Point/distance_to.md");
+        assert!(distance_to.contains("<span class=\"pre\">&self</span>"));
+    }
+
+    #[test]
+    fn unit_returns_are_shown_when_configured() {
+        let helper = |toml: &str| {
+            collections(&temp_dir("unit_returns_are_shown_when_configured"), toml)
+                .render("fixture::utils::helper")
+                .unwrap()
+        };
+        assert!(!helper(FUNCTIONS).contains("→"));
+        let toml = format!("show_unit_return = true\n{}", FUNCTIONS);
+        assert!(helper(&toml).contains("<span class=\"sig-paren\">)</span>\n → ()\n    </dt>"));
+    }
+
+    #[test]
+    fn aliased_types_are_linked() {
+        let collections = collections(
+            &temp_dir("aliased_types_are_linked"),
+            r#"
+[[packages]]
+name = "fixture"
+kind = "type_alias"
+json_path = "{fixture}"
+"#,
+        );
+        let page = collections.render("fixture::Shared").unwrap();
+        assert!(page.contains(concat!(
+            "&lt;T&gt; = <a href=\"https://doc.rust-lang.org/nightly/std/sync/struct.Arc.html\">Arc</a>",
+            "&lt;<a href=\"https://doc.rust-lang.org/nightly/std/sync/struct.Mutex.html\">Mutex</a>",
+            "&lt;T&gt;&gt;"
+        )));
+    }
+
+    #[test]
+    fn where_clauses_of_associated_types_are_rendered() {
+        let collections = collections(
+            &temp_dir("where_clauses_of_associated_types_are_rendered"),
+            TRAITS,
+        );
+        let page = collections.render("fixture::Area").unwrap();
+        assert!(page.contains(concat!(
+            "| type Out <span class=\"sig-where\">where Self: ",
+            "<a href=\"https://doc.rust-lang.org/nightly/std/marker/trait.Sized.html\">Sized</a>",
+            "</span> | Output of the shape. |"
+        )));
+    }
+
+    #[test]
+    fn items_of_extern_blocks_are_qualified() {
+        let dir = temp_dir("items_of_extern_blocks_are_qualified");
+        let toml = format!(
+            "{}\n{}",
+            FUNCTIONS,
+            r#"
+[[packages]]
+name = "fixture"
+kind = "static"
+json_path = "{fixture}"
+"#
+        );
+        collections(&dir, &toml).extract().unwrap();
+
+        let ffi_area = read_to_string(dir.join("out/fixture/utils/ffi_area.md")).unwrap();
+        assert!(ffi_area.contains(
+            "<em class=\"property\">\n        <span class=\"pre\">unsafe extern \"C\"</span>\n    </em>"
+        ));
+        assert!(ffi_area.ends_with("Area computed by the C library.\n"));
+        let errno = read_to_string(dir.join("out/fixture/utils/ERRNO.md")).unwrap();
+        assert!(errno.contains("<span class=\"pre\">unsafe static</span>"));
+        // No initializer follows the type
+        assert!(errno.contains(
+            ": <a href=\"https://doc.rust-lang.org/nightly/std/primitive.i32.html\">i32</a>\n    </dt>"
+        ));
+    }
+}
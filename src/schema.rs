@@ -0,0 +1,127 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+// Pages written with `output_format = "json"`. Fields may be added without notice, but any change
+// to existing fields must bump `SCHEMA_VERSION`.
+
+use rustdoc_types::{ItemEnum, ItemKind};
+use serde::{Deserialize, Serialize};
+
+use crate::repr::Repr;
+use crate::segment::CachedItem;
+use crate::utils::caption;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Page {
+    pub schema_version: u32,
+    pub name: String,
+    pub kind: String,
+    pub path: Vec<String>,
+    // Markdown, with links to the other items as if their pages were `.md` files
+    pub docs: String,
+    // HTML of the signature following the name, for functions, constants and statics
+    pub signature: Option<String>,
+    pub external_link: String,
+    pub parent: Option<Vec<String>>,
+    pub children: Vec<Child>,
+}
+
+// Variant, method or item of a module listed on the page
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Child {
+    pub name: String,
+    pub kind: String,
+    pub path: Vec<String>,
+    pub caption: String,
+}
+
+impl From<&CachedItem> for Page {
+    fn from(item: &CachedItem) -> Self {
+        let signature = match &item.item().unwrap().inner {
+            inner @ (ItemEnum::Function(_) | ItemEnum::Constant(_) | ItemEnum::Static(_)) => {
+                Some(inner.repr(item))
+            }
+            _ => None,
+        };
+        let children = (item.variants().into_iter())
+            .chain(item.associated_methods())
+            .chain(item.module_items())
+            .map(|child| Child {
+                name: child.name().to_string(),
+                kind: kind_name(child.kind()),
+                path: child.path().iter().map(|p| p.to_string()).collect(),
+                caption: (child.item())
+                    .map(|item| caption(item).to_string())
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        Page {
+            schema_version: SCHEMA_VERSION,
+            name: item.name().to_string(),
+            kind: kind_name(item.kind()),
+            path: item.path().iter().map(|p| p.to_string()).collect(),
+            docs: item.docs(),
+            signature,
+            external_link: item.external_link().to_string(),
+            parent: (item.item_summary().is_none())
+                .then(|| item.parent())
+                .flatten()
+                .map(|parent| parent.path().iter().map(|p| p.to_string()).collect()),
+            children,
+        }
+    }
+}
+
+fn kind_name(kind: &ItemKind) -> String {
+    serde_plain::to_string(kind).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{collections, temp_dir};
+
+    // Rendered page of schema version 1; changing it requires bumping `SCHEMA_VERSION`
+    const PAGE_V1: &str = include_str!("../tests/fixtures/page_v1.json");
+
+    #[test]
+    fn pages_match_the_schema_version() {
+        let collections = collections(
+            &temp_dir("pages_match_the_schema_version"),
+            r#"
+output_format = "json"
+
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+"#,
+        );
+
+        assert_eq!(
+            SCHEMA_VERSION, 1,
+            "add a golden page for the new schema version"
+        );
+        assert_eq!(
+            collections.render("fixture::shapes::Point").unwrap(),
+            PAGE_V1
+        );
+    }
+}
@@ -15,20 +15,33 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::cell::{OnceCell, RefCell};
-use std::collections::HashMap;
-use std::fs::{create_dir_all, File};
-use std::io::{BufReader, Write};
-use std::iter::zip;
-use std::path::PathBuf;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::env;
+use std::fs::{copy, create_dir_all, read_to_string};
+use std::hash::{Hash, Hasher};
+use std::panic;
+use std::path::{Component, Path, PathBuf};
 use std::rc::Rc;
+use std::thread;
 
-use anyhow::Error;
-use rustdoc_types::{Crate, Id, Item, ItemEnum, ItemKind, ItemSummary};
+use regex::RegexBuilder;
+use rustdoc_types::{
+    Crate, Id, Impl, Item, ItemEnum, ItemKind, ItemSummary, MacroKind, StructKind, Type,
+};
+use serde::{Deserialize, Serialize};
 
-use crate::repr::Repr;
-use crate::utils::hide_code_block_lines;
-use crate::{Config, Package};
+use crate::error::ExtractError;
+use crate::repr::{LinkVersion, OutputFormat, RenderOptions, Repr, DEFAULT_LINK_TEMPLATE};
+use crate::schema::Page;
+use crate::summary::{jupyter_book_toc, Summary};
+use crate::utils::{
+    escape_inline_code, escape_raw_html, hide_code_block_lines, relative_links, relative_path,
+    resolve_intra_doc_links, resolve_reference_links, rewrite_relative_links, section_alerts,
+    strip_leading_heading, write_atomic,
+};
+use crate::{Config, Package, Selector};
 
 #[derive(Debug)]
 pub struct ExportOption {
@@ -37,30 +50,170 @@ pub struct ExportOption {
     kind: ItemKind,
 }
 
+#[derive(Debug, Serialize)]
+pub struct ItemMetadata {
+    pub name: String,
+    pub kind: ItemKind,
+    pub path: Vec<String>,
+    pub external_link: String,
+}
+
 #[derive(Debug)]
 pub struct SegmentCollections {
     output_root: PathBuf,
     items: Vec<Rc<CachedItem>>,
+    summary: Option<Summary>,
+    pool: Rc<ItemPool>,
 }
 
 impl SegmentCollections {
-    pub fn extract(&self) -> Result<(), Error> {
+    // Build the rustdoc JSON of the configured packages and collect the items to extract
+    pub fn from_config(config: Config) -> Result<Self, ExtractError> {
+        config.try_into()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // Metadata of the collected items, as resolved before rendering
+    pub fn items(&self) -> Vec<ItemMetadata> {
+        self.items
+            .iter()
+            .map(|item| ItemMetadata {
+                name: item.name().to_string(),
+                kind: item.kind().clone(),
+                path: item.path().iter().map(|p| p.to_string()).collect(),
+                external_link: item.external_link().to_string(),
+            })
+            .collect()
+    }
+
+    // Number of hits of each construct rendered as a placeholder
+    pub fn unsupported_constructs(&self) -> BTreeMap<String, usize> {
+        self.pool.unsupported.borrow().clone()
+    }
+
+    // Names and versions of the external crates linked from the rendered pages
+    pub fn external_crates(&self) -> BTreeMap<String, Option<String>> {
+        self.pool.external_crates.borrow().clone()
+    }
+
+    // Page of the item of a fully qualified path, e.g. `mycrate::foo::Bar`
+    pub fn render(&self, path: &str) -> Result<String, ExtractError> {
+        let id = find_item(&self.pool, path).ok_or_else(|| {
+            ExtractError::Config(format!("item `{}` not found in the packages", path))
+        })?;
+        self.render_page(&self.pool.clone().get(&id))
+    }
+
+    fn render_page(&self, item: &CachedItem) -> Result<String, ExtractError> {
+        // Pages are rendered from the index of the crate, which lacks items only listed in its
+        // paths, e.g. private items of public paths. Unsupported constructs of indexed items are
+        // rendered as placeholders instead.
+        if item.item().is_none() {
+            return Err(ExtractError::Render {
+                item: item.path().join("::"),
+                message: "item missing from the index of its crate".to_string(),
+            });
+        }
+
+        Ok(match self.pool.options.output_format {
+            OutputFormat::Json => serde_json::to_string_pretty(&Page::from(item))?,
+            _ => item.repr(item),
+        })
+    }
+
+    // File the page of an item is written to
+    fn page_file(&self, item: &CachedItem) -> PathBuf {
+        let page = item.page_path();
+        let (name, path) = page.split_last().unwrap();
+        self.output_root.join(PathBuf::from_iter(path)).join(
+            match self.pool.options.output_format {
+                OutputFormat::Json => format!("{}.json", name),
+                _ => format!("{}.md", name),
+            },
+        )
+    }
+
+    // Relative links of the written pages to files missing from the output, as pairs of the page
+    // and the link destination
+    pub fn check_links(&self) -> Result<Vec<(PathBuf, String)>, ExtractError> {
+        // Links of a single file are anchors within it
+        if self.pool.options.single_file.is_some()
+            || self.pool.options.output_format == OutputFormat::Json
+        {
+            return Ok(vec![]);
+        }
+
+        let mut broken = vec![];
         for item in &self.items {
-            let (root, filename) = item
-                .path()
-                .as_slice()
-                .split_last()
-                .map(|(name, path)| {
-                    let root = self.output_root.join(PathBuf::from_iter(path));
-                    let file = root.join(format!("{}.md", name));
-                    (root, file)
-                })
-                .unwrap();
+            let file = self.page_file(item);
+            let dir = file.parent().unwrap_or(Path::new(""));
+            for dest in relative_links(&read_to_string(&file)?) {
+                if !dir.join(&dest).exists() {
+                    broken.push((file.clone(), dest));
+                }
+            }
+        }
+        Ok(broken)
+    }
+
+    pub fn extract(&self) -> Result<(), ExtractError> {
+        let mut files = vec![];
+        let mut pages = vec![];
+        let single_file = self.pool.options.single_file.as_deref();
+        let format = self.pool.options.output_format;
 
-            create_dir_all(&root)?;
+        for item in &self.items {
+            let content = self.render_page(item)?;
+
+            if single_file.is_some() {
+                pages.push(match format {
+                    OutputFormat::Json => content,
+                    _ => format!("<a id=\"{}\"></a>\n\n{}", item.anchor(), content),
+                });
+                continue;
+            }
 
-            let mut file = File::create(filename)?;
-            file.write_all(item.repr(item).as_bytes())?;
+            let filename = self.page_file(item);
+            if let Some(root) = filename.parent() {
+                create_dir_all(root)?;
+            }
+            write_atomic(&filename, &content)?;
+
+            files.push(item.page_path());
+        }
+
+        if let Some(single_file) = single_file {
+            let filename = self.output_root.join(single_file);
+            if let Some(root) = filename.parent() {
+                create_dir_all(root)?;
+            }
+            let content = match format {
+                OutputFormat::Json => format!("[\n{}\n]", pages.join(",\n")),
+                _ => pages.join("\n\n"),
+            };
+            write_atomic(&filename, &(content + "\n"))?;
+
+            let page = Path::new(single_file).with_extension("");
+            files.push(
+                page.iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect(),
+            );
+        }
+
+        if let Some(Summary::JupyterBook) = self.summary {
+            // Jupyter Book requires a root page
+            let index = self.output_root.join("index.md");
+            if !index.exists() {
+                write_atomic(&index, "# API Reference\n")?;
+            }
+            write_atomic(
+                &self.output_root.join("_toc.yml"),
+                &jupyter_book_toc("index", &files),
+            )?;
         }
 
         Ok(())
@@ -68,53 +221,112 @@ impl SegmentCollections {
 }
 
 impl TryFrom<Config> for SegmentCollections {
-    type Error = Error;
+    type Error = ExtractError;
 
     fn try_from(value: Config) -> Result<Self, Self::Error> {
         let manifest_path = value.manifest_path.as_deref().unwrap_or("Cargo.toml");
-        let output_root = PathBuf::from(value.output_path);
-        let mut packages = HashMap::new();
+        let cap_lints = value.cap_lints.as_deref().unwrap_or("warn");
+        let toolchain = value.toolchain.as_deref().unwrap_or("nightly");
+        let (output_root, kind_dirs) = split_output_path(&value.output_path);
         let mut extract_options = vec![];
 
-        for package in value.packages {
-            if packages.get(&package.name).is_none() {
-                let builder = rustdoc_json::Builder::default()
-                    .manifest_path(manifest_path)
-                    .package(&package.name)
-                    .toolchain("nightly")
-                    .all_features(true)
-                    .clear_target_dir();
-
-                let json_path = builder.build()?;
-                let file = File::open(json_path)?;
-                let reader = BufReader::new(file);
-                let crate_: Crate = serde_json::from_reader(reader)?;
-
-                packages.insert(package.name.clone(), crate_);
+        // Packages are built with the options of their first entry, and then the dependencies
+        // they re-export
+        let mut builds: Vec<&Package> = vec![];
+        for package in &value.packages {
+            if !builds.iter().any(|build| build.name == package.name) {
+                builds.push(package);
             }
+        }
+        let build_options = BuildOptions {
+            manifest_path,
+            toolchain,
+            cap_lints,
+            cache_dir: value.cache_dir.as_deref().map(Path::new),
+            require_format_version: value.require_format_version,
+        };
+        let mut packages = build_crates(&builds, build_options)?;
 
-            let kind = serde_plain::from_str(&package.kind)?;
-            let module_path = package
-                .module_path
-                .as_deref()
-                .map(|s| s.split("::").collect());
+        let deps = (value.packages.iter())
+            .filter(|package| package.follow_reexports)
+            .flat_map(|package| reexported_crates(packages.get(&package.name).unwrap()))
+            .filter(|dep| !packages.contains_key(dep))
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .map(|name| Package {
+                name,
+                ..Default::default()
+            })
+            .collect::<Vec<Package>>();
+        packages.extend(build_crates(
+            &deps.iter().collect::<Vec<&Package>>(),
+            build_options,
+        )?);
 
-            extract_options.push(ExportOption {
-                package,
-                module_path,
-                kind,
-            });
+        // Items of the config take the place of those selected by the packages
+        let selected = match value.items.is_empty() {
+            true => value.packages,
+            false => vec![],
+        };
+        for package in selected {
+            // Without selectors, the package selects items by its own `kind` and `module_path`
+            let selectors = match package.selectors.is_empty() {
+                true => vec![Selector::default()],
+                false => package.selectors.clone(),
+            };
+            for selector in selectors {
+                let kind = (selector.kind.as_deref())
+                    .or(package.kind.as_deref())
+                    .or(value.default_kind.as_deref())
+                    .ok_or_else(|| {
+                        ExtractError::Config(format!(
+                            "no kind specified for package `{}`",
+                            package.name
+                        ))
+                    })?;
+                let kind = serde_plain::from_str(kind).map_err(|_| {
+                    ExtractError::Config(format!(
+                        "unknown kind `{}` of package `{}`",
+                        kind, package.name
+                    ))
+                })?;
+                let module_path = (selector.module_path.as_deref())
+                    .or(package.module_path.as_deref())
+                    .map(|s| s.split("::").collect());
+
+                extract_options.push(ExportOption {
+                    package: package.clone(),
+                    module_path,
+                    kind,
+                });
+            }
         }
 
         let pool = Rc::new(ItemPool {
             crates: packages,
+            options: value.options,
+            source_root: Path::new(manifest_path)
+                .parent()
+                .map(|path| path.to_path_buf())
+                .unwrap_or_default(),
+            output_root: output_root.clone(),
+            kind_dirs,
             cached_items: RefCell::new(HashMap::new()),
             extract_items: RefCell::new(vec![]),
+            unsupported: RefCell::new(BTreeMap::new()),
+            external_crates: RefCell::new(BTreeMap::new()),
         });
 
         // Collect items to be extract
         let mut items = vec![];
-        for option in extract_options {
+        for path in &value.items {
+            let id = find_item(&pool, path).ok_or_else(|| {
+                ExtractError::Config(format!("item `{}` not found in the packages", path))
+            })?;
+            let item = pool.clone().get(&id);
+            items.extend(item.associated_methods().into_iter().chain([item]));
+        }
+        for option in &extract_options {
             let crate_ = pool.crates.get(&option.package.name).unwrap();
             items.extend(
                 crate_
@@ -132,23 +344,445 @@ impl TryFrom<Config> for SegmentCollections {
                     .flat_map(|(id, _)| {
                         let id = ItemId::new(&option.package.name, id);
                         let item = pool.clone().get(&id);
-                        let methods = item.associated_methods();
-                        methods.into_iter().chain([item])
+                        // Modules are extracted along with the items defined in them
+                        let mut items = item.module_items();
+                        items.push(item);
+                        items.into_iter().flat_map(|item| {
+                            let methods = item.associated_methods();
+                            methods.into_iter().chain([item])
+                        })
                     }),
-            )
+            );
         }
+        // Re-exports are placed after the items of all packages, which keep their own paths when
+        // re-exported from another configured package
+        for option in &extract_options {
+            if option.package.follow_reexports || option.package.expand_glob_reexports {
+                items.extend(reexported_items(&pool, option));
+            }
+        }
+
+        // Items collected by overlapping options, or placed at the same path, are written once
+        let mut pages = HashSet::new();
+        items.retain(|item| pages.insert(item.page_path()));
 
         pool.extract_items.borrow_mut().extend(items.clone());
 
-        Ok(Self { output_root, items })
+        Ok(Self {
+            output_root,
+            items,
+            summary: value.summary,
+            pool,
+        })
+    }
+}
+
+// Build the packages on threads of their own. Cargo holds a lock on the target directory while
+// compiling, so this mostly overlaps resolving the metadata and parsing the JSON.
+fn build_crates(
+    packages: &[&Package],
+    options: BuildOptions,
+) -> Result<HashMap<String, Crate>, ExtractError> {
+    thread::scope(|scope| {
+        let builds = (packages.iter())
+            .map(|package| scope.spawn(move || build_crate(package, options)))
+            .collect::<Vec<_>>();
+
+        (packages.iter().zip(builds))
+            .map(|(package, build)| {
+                let crate_ = build
+                    .join()
+                    .unwrap_or_else(|err| panic::resume_unwind(err))?;
+                Ok((package.name.clone(), crate_))
+            })
+            .collect()
+    })
+}
+
+#[derive(Clone, Copy)]
+struct BuildOptions<'a> {
+    manifest_path: &'a str,
+    toolchain: &'a str,
+    cap_lints: &'a str,
+    // Directory keeping the JSON of packages to be reused while their sources are unchanged
+    cache_dir: Option<&'a Path>,
+    require_format_version: Option<u32>,
+}
+
+fn build_crate(package: &Package, options: BuildOptions) -> Result<Crate, ExtractError> {
+    if let Some(json_path) = &package.json_path {
+        return read_crate(Path::new(json_path), &package.name, options);
+    }
+
+    let cache = cache_path(package, options);
+    if let Some(crate_) =
+        (cache.as_deref()).and_then(|cache| cached_crate(cache, &package.name, options))
+    {
+        return Ok(crate_);
+    }
+
+    let builder = rustdoc_json::Builder::default()
+        .manifest_path(options.manifest_path)
+        .package(&package.name)
+        .toolchain(options.toolchain)
+        .features(&package.features)
+        .no_default_features(package.no_default_features)
+        .all_features(all_features(package))
+        .cap_lints(Some(options.cap_lints))
+        .clear_target_dir();
+
+    let json_path = builder.build().map_err(|source| ExtractError::Build {
+        package: package.name.clone(),
+        source,
+    })?;
+    if let Some(cache) = &cache {
+        cache.parent().map(create_dir_all).unwrap_or(Ok(()))?;
+        copy(&json_path, cache)?;
     }
+
+    read_crate(&json_path, &package.name, options)
+}
+
+// All features are enabled unless the package selects its own
+fn all_features(package: &Package) -> bool {
+    package
+        .all_features
+        .unwrap_or(package.features.is_empty() && !package.no_default_features)
+}
+
+// Cached JSON of a package. Packages of other workspaces, or built with other options, are cached
+// apart.
+fn cache_path(package: &Package, options: BuildOptions) -> Option<PathBuf> {
+    let mut hasher = DefaultHasher::new();
+    (&package.features, package.no_default_features).hash(&mut hasher);
+    all_features(package).hash(&mut hasher);
+    (options.manifest_path, options.toolchain, options.cap_lints).hash(&mut hasher);
+    (options.cache_dir)
+        .map(|dir| dir.join(format!("{}-{:016x}.json", package.name, hasher.finish())))
+}
+
+// The format version is read ahead of the crate, as JSON of other versions may not parse at all
+fn read_crate(
+    json_path: &Path,
+    package: &str,
+    options: BuildOptions,
+) -> Result<Crate, ExtractError> {
+    #[derive(Deserialize)]
+    struct FormatVersion {
+        format_version: u32,
+    }
+
+    let json = read_to_string(json_path)?;
+    if let Some(required) = options.require_format_version {
+        let found = serde_json::from_str::<FormatVersion>(&json)?.format_version;
+        if found != required {
+            return Err(ExtractError::FormatVersion {
+                package: package.to_string(),
+                found,
+                required,
+            });
+        }
+    }
+
+    Ok(serde_json::from_str(&json)?)
+}
+
+// Crate of a cached JSON, unless the manifests, any source file of the crate or any file included
+// by the sources were modified after it was cached
+fn cached_crate(cache: &Path, package: &str, options: BuildOptions) -> Option<Crate> {
+    let modified = |path: &Path| path.metadata().and_then(|meta| meta.modified()).ok();
+    let cached_at = modified(cache)?;
+    let crate_ = read_crate(cache, package, options).ok()?;
+
+    let manifest_path = Path::new(options.manifest_path);
+    let source_root = manifest_path.parent().unwrap_or(Path::new(""));
+    let sources = (crate_.index.values())
+        .filter(|item| item.crate_id == 0)
+        .filter_map(|item| item.span.as_ref())
+        .map(|span| source_root.join(&span.filename))
+        .collect::<BTreeSet<PathBuf>>();
+    // The manifest of the package is next to `src`, as in `asset_link`
+    let package_manifest = (crate_.index.get(&crate_.root))
+        .and_then(|item| item.span.as_ref())
+        .and_then(|span| span.filename.parent())
+        .map(|dir| match dir.ends_with("src") {
+            true => dir.parent().unwrap_or(dir),
+            false => dir,
+        })
+        .map(|dir| source_root.join(dir).join("Cargo.toml"));
+
+    // Included files are not listed in the JSON, e.g. a README included as the docs of the crate
+    let included = (sources.iter())
+        .map(|source| included_files(source))
+        .collect::<Option<Vec<_>>>()?;
+
+    let unchanged = ([manifest_path.to_path_buf()].into_iter())
+        .chain(package_manifest)
+        .chain(sources)
+        .chain(included.into_iter().flatten())
+        .all(|path| modified(&path).is_some_and(|time| time < cached_at));
+    unchanged.then_some(crate_)
+}
+
+// Files included by `include_str!` or `include_bytes!` in a source file, relative to it. Paths
+// other than string literals, e.g. `concat!(env!("CARGO_MANIFEST_DIR"), ...)`, are resolved while
+// building only, so the crate is built again instead.
+fn included_files(source: &Path) -> Option<Vec<PathBuf>> {
+    let text = read_to_string(source).ok()?;
+    let dir = source.parent().unwrap_or(Path::new(""));
+    (text.match_indices("include_"))
+        .filter_map(|(i, _)| {
+            let rest = &text[i + "include_".len()..];
+            (rest.strip_prefix("str!"))
+                .or_else(|| rest.strip_prefix("bytes!"))
+                .and_then(|rest| rest.trim_start().strip_prefix('('))
+        })
+        .map(|args| {
+            let literal = args.trim_start().strip_prefix('"')?;
+            literal.split_once('"').map(|(path, _)| dir.join(path))
+        })
+        .collect()
+}
+
+// Crates of the sysroot can not be built with cargo.
+const SYSROOT_CRATES: [&str; 5] = ["std", "core", "alloc", "proc_macro", "test"];
+
+// Names of external crates with items re-exported by `crate_`.
+fn reexported_crates(crate_: &Crate) -> BTreeSet<String> {
+    crate_
+        .index
+        .values()
+        .filter_map(|item| match &item.inner {
+            ItemEnum::Import(import) => import.id.as_ref(),
+            _ => None,
+        })
+        .filter_map(|id| crate_.paths.get(id))
+        .filter_map(|summ| crate_.external_crates.get(&summ.crate_id))
+        .map(|ext_crate| ext_crate.name.clone())
+        .filter(|name| !SYSROOT_CRATES.contains(&name.as_str()))
+        .collect()
+}
+
+// Items re-exported by the package of `option`, which are placed under the path of the re-export
+// instead of their original path. Items of dependencies are included with `follow_reexports`, and
+// items of glob re-exported modules with `expand_glob_reexports`.
+fn reexported_items(pool: &Rc<ItemPool>, option: &ExportOption) -> Vec<Rc<CachedItem>> {
+    let package = &option.package;
+    let crate_ = pool.crates.get(&package.name).unwrap();
+    let module_paths = module_paths(crate_);
+
+    crate_
+        .index
+        .values()
+        .filter_map(|item| match &item.inner {
+            ItemEnum::Import(import) => import.id.as_ref().map(|id| (item, import, id)),
+            _ => None,
+        })
+        .filter_map(|(item, import, id)| {
+            let (pkg, target_id) = resolve_reexport(pool, &package.name, id)?;
+            let target_crate = pool.crates.get(&pkg)?;
+            let module_path = module_paths.get(&item.id)?;
+
+            let targets = if import.glob && package.expand_glob_reexports {
+                match &target_crate.index.get(target_id)?.inner {
+                    ItemEnum::Module(module) => (module.items.iter())
+                        .filter_map(|child| target_crate.index.get(child))
+                        .filter_map(|child| Some((child.name.clone()?, &child.id)))
+                        .collect(),
+                    _ => vec![],
+                }
+            } else if !import.glob && package.follow_reexports && pkg != package.name {
+                vec![(import.name.clone(), target_id)]
+            } else {
+                vec![]
+            };
+
+            Some(targets.into_iter().filter_map(move |(name, id)| {
+                let kind = (target_crate.paths.get(id).map(|summ| summ.kind.clone()))
+                    .or_else(|| Some(item_kind(target_crate.index.get(id)?).clone()))?;
+                let path: Vec<String> = module_path.iter().cloned().chain([name]).collect();
+                Some((kind, ItemId::new(&pkg, id), path))
+            }))
+        })
+        .flatten()
+        .filter(|(kind, _, _)| kind == &option.kind)
+        .filter(|(_, _, path)| {
+            option
+                .module_path
+                .as_ref()
+                .map(|p| path.iter().collect::<PathBuf>().starts_with(p))
+                .unwrap_or(true)
+        })
+        .flat_map(|(_, dep_id, path)| {
+            let item = pool.clone().insert_with_path(&dep_id, Some(path));
+            let methods = item.associated_methods();
+            methods.into_iter().chain([item])
+        })
+        .collect()
+}
+
+// Resolve the target of a re-export in package `pkg` to the package and id of its definition.
+fn resolve_reexport<'a>(pool: &'a ItemPool, pkg: &str, id: &'a Id) -> Option<(String, &'a Id)> {
+    let crate_ = pool.crates.get(pkg)?;
+    if crate_.index.contains_key(id) {
+        return Some((pkg.to_string(), id));
+    }
+
+    let summ = crate_.paths.get(id)?;
+    let ext_crate = crate_.external_crates.get(&summ.crate_id)?;
+    (pool.crates.get(&ext_crate.name)?.paths.iter())
+        .find(|(_, dep_summ)| dep_summ.crate_id == 0 && dep_summ.path == summ.path)
+        .map(|(dep_id, _)| (ext_crate.name.clone(), dep_id))
+}
+
+// Kind of items without `ItemSummary`, e.g. methods and impl blocks.
+fn item_kind(item: &Item) -> &'static ItemKind {
+    match &item.inner {
+        ItemEnum::Module(_) => &ItemKind::Module,
+        ItemEnum::ExternCrate { .. } => &ItemKind::ExternCrate,
+        ItemEnum::Import(_) => &ItemKind::Import,
+        ItemEnum::Union(_) => &ItemKind::Union,
+        ItemEnum::Struct(_) => &ItemKind::Struct,
+        ItemEnum::StructField(_) => &ItemKind::StructField,
+        ItemEnum::Enum(_) => &ItemKind::Enum,
+        ItemEnum::Variant(_) => &ItemKind::Variant,
+        ItemEnum::Function(_) => &ItemKind::Function,
+        ItemEnum::Trait(_) => &ItemKind::Trait,
+        ItemEnum::TraitAlias(_) => &ItemKind::TraitAlias,
+        ItemEnum::Impl(_) => &ItemKind::Impl,
+        ItemEnum::TypeAlias(_) => &ItemKind::TypeAlias,
+        ItemEnum::OpaqueTy(_) => &ItemKind::OpaqueTy,
+        ItemEnum::Constant(_) => &ItemKind::Constant,
+        ItemEnum::Static(_) => &ItemKind::Static,
+        ItemEnum::ForeignType => &ItemKind::ForeignType,
+        ItemEnum::Macro(_) => &ItemKind::Macro,
+        ItemEnum::ProcMacro(proc_macro) => match proc_macro.kind {
+            MacroKind::Bang => &ItemKind::Macro,
+            MacroKind::Attr => &ItemKind::ProcAttribute,
+            MacroKind::Derive => &ItemKind::ProcDerive,
+        },
+        ItemEnum::Primitive(_) => &ItemKind::Primitive,
+        ItemEnum::AssocConst { .. } => &ItemKind::AssocConst,
+        ItemEnum::AssocType { .. } => &ItemKind::AssocType,
+    }
+}
+
+// Map items to the path of the module they belong to.
+fn module_paths(crate_: &Crate) -> HashMap<&Id, Vec<String>> {
+    let mut paths = HashMap::new();
+    let mut modules = vec![];
+
+    if let Some(root) = crate_.index.get(&crate_.root) {
+        modules.push((root, root.name.iter().cloned().collect::<Vec<String>>()));
+    }
+
+    while let Some((module, path)) = modules.pop() {
+        if let ItemEnum::Module(ref inner) = module.inner {
+            for id in &inner.items {
+                if let Some(
+                    item @ Item {
+                        name: Some(name),
+                        inner: ItemEnum::Module(_),
+                        ..
+                    },
+                ) = crate_.index.get(id)
+                {
+                    let child_path = path.iter().cloned().chain([name.clone()]).collect();
+                    modules.push((item, child_path));
+                }
+                paths.insert(id, path.clone());
+            }
+        }
+    }
+
+    paths
+}
+
+// Kinds of items listed on the page of their module, submodules being the index of a directory
+// below it
+const MODULE_ITEM_KINDS: [ItemKind; 9] = [
+    ItemKind::Module,
+    ItemKind::Struct,
+    ItemKind::Enum,
+    ItemKind::Union,
+    ItemKind::Trait,
+    ItemKind::Function,
+    ItemKind::TypeAlias,
+    ItemKind::Constant,
+    ItemKind::Static,
+];
+
+// Kind of an item in the URL of its rustdoc page
+fn url_kind(kind: &ItemKind) -> String {
+    match kind {
+        ItemKind::TypeAlias => "type".to_string(),
+        kind => serde_plain::to_string(kind).unwrap(),
+    }
+}
+
+// Split `output_path` into the output root and the directories below it routing the pages by
+// the `{kind}` placeholder
+pub fn split_output_path(output_path: &str) -> (PathBuf, Vec<String>) {
+    let mut output_root = PathBuf::new();
+    let mut kind_dirs = vec![];
+    for component in Path::new(output_path).iter() {
+        let component = component.to_string_lossy();
+        if kind_dirs.is_empty() && !component.contains("{kind}") {
+            output_root.push(&*component);
+        } else {
+            kind_dirs.push(component.to_string());
+        }
+    }
+    if output_root.as_os_str().is_empty() {
+        output_root.push(".");
+    }
+
+    (output_root, kind_dirs)
+}
+
+// Components of the absolute form of `path`, with `.` and `..` resolved lexically as the path may
+// not exist yet
+fn absolute_components(path: &Path) -> Vec<String> {
+    let path = env::current_dir().unwrap_or_default().join(path);
+    let mut components = vec![];
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                components.pop();
+            }
+            component => components.push(component.as_os_str().to_string_lossy().to_string()),
+        }
+    }
+
+    components
+}
+
+// Find an item defined in one of the built crates by its fully qualified path
+fn find_item(pool: &ItemPool, path: &str) -> Option<ItemId> {
+    let path: Vec<&str> = path.split("::").collect();
+    pool.crates.iter().find_map(|(pkg, crate_)| {
+        crate_
+            .paths
+            .iter()
+            .find(|(_, summ)| summ.crate_id == 0 && summ.path.iter().eq(path.iter()))
+            .map(|(id, _)| ItemId::new(pkg, id))
+    })
 }
 
 #[derive(Debug)]
 pub struct ItemPool {
     crates: HashMap<String, Crate>,
+    pub options: RenderOptions,
+    // Directory of the manifest, to which paths of source files are relative
+    source_root: PathBuf,
+    output_root: PathBuf,
+    kind_dirs: Vec<String>,
     cached_items: RefCell<HashMap<ItemId, Rc<CachedItem>>>,
     extract_items: RefCell<Vec<Rc<CachedItem>>>,
+    unsupported: RefCell<BTreeMap<String, usize>>,
+    // Crates outside of the built packages linked from the pages, with their versions if known
+    external_crates: RefCell<BTreeMap<String, Option<String>>>,
 }
 
 impl ItemPool {
@@ -156,17 +790,81 @@ impl ItemPool {
         self.insert_with_path(id, None)
     }
 
+    pub fn is_extracted(&self, id: &ItemId) -> bool {
+        self.extract_items
+            .borrow()
+            .iter()
+            .any(|item| &item.id == id)
+    }
+
+    // Items of the configured packages are always linked, others only if their crate is listed in
+    // `link_crates`.
+    pub fn links_to_crate(&self, name: &str) -> bool {
+        match &self.options.link_crates {
+            Some(crates) => {
+                crates.iter().any(|krate| krate == name)
+                    || self.crates.keys().any(|pkg| pkg.replace('-', "_") == name)
+            }
+            None => true,
+        }
+    }
+
+    // Root of the docs of the sysroot crates, which are not published to docs.rs. Pinned links
+    // follow the toolchain the packages were documented with, as in the `html_root_url` rustdoc
+    // gives `std`, e.g. `nightly/`, and latest links the stable release.
+    pub fn sysroot_root_url(&self) -> String {
+        let toolchain_url = (self.crates.values())
+            .flat_map(|crate_| crate_.external_crates.values())
+            .find(|ext_crate| ext_crate.name == "std")
+            .and_then(|ext_crate| ext_crate.html_root_url.clone());
+        match (self.options.link_version, toolchain_url) {
+            (LinkVersion::Pinned, Some(url)) => url,
+            _ => "https://doc.rust-lang.org/stable/".to_string(),
+        }
+    }
+
+    fn record_external_crate(&self, item: &CachedItem) {
+        let name = item.crate_name();
+        if self.crates.keys().any(|pkg| pkg.replace('-', "_") == name) {
+            return;
+        }
+
+        let re_docs_rs = RegexBuilder::new(r"^https://docs\.rs/[^/]+/(?<version>[^/]+)/$")
+            .build()
+            .unwrap();
+        let version = (re_docs_rs.captures(&item.html_root_url()))
+            .map(|cap| cap["version"].to_string())
+            .filter(|version| version != "latest");
+        self.external_crates
+            .borrow_mut()
+            .entry(name.to_string())
+            .or_insert(version);
+    }
+
     fn insert_with_path(self: Rc<Self>, id: &ItemId, path: Option<Vec<String>>) -> Rc<CachedItem> {
         let cached_item = self.cached_items.borrow().get(id).cloned();
 
-        if let Some(cached_item) = cached_item {
-            cached_item
-        } else {
-            let item = CachedItem::new(self.clone(), id.clone(), path);
-            self.cached_items
-                .borrow_mut()
-                .insert(id.clone(), item.clone());
-            item
+        match cached_item {
+            // Placed at another path than the cached item, e.g. an item of a dependency collected
+            // from its own package and re-exported by another
+            Some(cached_item)
+                if (path.as_ref()).is_some_and(|path| {
+                    cached_item
+                        .path()
+                        .into_iter()
+                        .ne(path.iter().map(|p| p.as_str()))
+                }) =>
+            {
+                CachedItem::new(self.clone(), id.clone(), path)
+            }
+            Some(cached_item) => cached_item,
+            None => {
+                let item = CachedItem::new(self.clone(), id.clone(), path);
+                self.cached_items
+                    .borrow_mut()
+                    .insert(id.clone(), item.clone());
+                item
+            }
         }
     }
 }
@@ -192,6 +890,10 @@ pub struct CachedItem {
     pub id: ItemId,
     path: Option<Vec<String>>,
     external_link: OnceCell<String>,
+    // Bounds of generic parameters not yet annotated in the signature being rendered
+    pub generic_bounds: RefCell<HashMap<String, String>>,
+    // Nesting depth of the type being rendered
+    pub type_depth: Cell<usize>,
 }
 
 impl CachedItem {
@@ -201,20 +903,22 @@ impl CachedItem {
             id: id.clone(),
             path,
             external_link: OnceCell::new(),
+            generic_bounds: RefCell::new(HashMap::new()),
+            type_depth: Cell::new(0),
         })
     }
 
     // Associated methods does not have `ItemSummary`, which means we needs to grab path infomation
     // from parent.
     pub fn associated_methods(&self) -> Vec<Rc<CachedItem>> {
-        if let Some(item) = self.item() {
-            let crate_ = self.pool.crates.get(&self.id.pkg).unwrap();
-            match &item.inner {
-                ItemEnum::Struct(ref struct_) => struct_
-                    .impls
-                    .iter()
+        let crate_ = self.pool.crates.get(&self.id.pkg).unwrap();
+        let ids: Vec<&Id> = match self.item().map(|item| &item.inner) {
+            Some(ItemEnum::Struct(_) | ItemEnum::Enum(_) | ItemEnum::Union(_)) => {
+                (self.impls().iter())
                     .filter_map(|id| crate_.index.get(id))
                     .filter_map(|item| match item.inner {
+                        // Methods of trait impls, including auto trait and blanket impls, are
+                        // documented with the traits
                         ItemEnum::Impl(ref impl_) => match impl_.trait_ {
                             Some(_) => None,
                             None => Some(&impl_.items),
@@ -222,24 +926,141 @@ impl CachedItem {
                         _ => None,
                     })
                     .flatten()
-                    .map(|id| {
-                        let item_id = ItemId::new(&self.id.pkg, id);
-                        let method_item = crate_.index.get(id).unwrap();
-                        (item_id, method_item)
-                    })
-                    .map(|(item_id, method_item)| {
-                        let name = method_item.name.as_deref();
-                        let path = (self.path().into_iter().chain(name))
-                            .map(|p| p.to_string())
-                            .collect();
-                        (item_id, path)
-                    })
-                    .map(|(item_id, path)| self.pool.clone().insert_with_path(&item_id, Some(path)))
-                    .collect(),
-                _ => vec![],
+                    .collect()
             }
-        } else {
-            vec![]
+            Some(ItemEnum::Trait(trait_)) => (trait_.items.iter())
+                .filter(|id| {
+                    matches!(
+                        crate_.index.get(id).map(|item| &item.inner),
+                        Some(ItemEnum::Function(_))
+                    )
+                })
+                .collect(),
+            _ => vec![],
+        };
+
+        let mut names = HashMap::new();
+        ids.into_iter()
+            .map(|id| {
+                let item_id = ItemId::new(&self.id.pkg, id);
+                let method_item = crate_.index.get(id).unwrap();
+                (item_id, method_item)
+            })
+            .map(|(item_id, method_item)| {
+                // Methods of the same name from different impl blocks get pages of their own,
+                // e.g. `new-2.md`
+                let name = method_item.name.clone().unwrap_or_default();
+                let count = names.entry(name.clone()).or_insert(0);
+                *count += 1;
+                let name = match count {
+                    1 => name,
+                    n => format!("{}-{}", name, n),
+                };
+                let path = (self.path().into_iter().map(|p| p.to_string()))
+                    .chain([name])
+                    .collect();
+                (item_id, path)
+            })
+            .map(|(item_id, path)| self.pool.clone().insert_with_path(&item_id, Some(path)))
+            .collect()
+    }
+
+    // Methods of a type grouped by the inherent impl blocks defining them
+    pub fn impl_methods(&self) -> Vec<(Rc<CachedItem>, Vec<Rc<CachedItem>>)> {
+        let crate_ = self.pool.crates.get(&self.id.pkg).unwrap();
+        let methods = self.associated_methods();
+        (self.impls().iter())
+            .filter_map(|id| match &crate_.index.get(id)?.inner {
+                ItemEnum::Impl(impl_) if impl_.trait_.is_none() => Some((id, impl_)),
+                _ => None,
+            })
+            .map(|(id, impl_)| {
+                let impl_methods = (methods.iter())
+                    .filter(|method| impl_.items.contains(&method.id.id))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let impl_item = self.pool.clone().get(&ItemId::new(&self.id.pkg, id));
+                (impl_item, impl_methods)
+            })
+            .filter(|(_, methods)| !methods.is_empty())
+            .collect()
+    }
+
+    // Impl blocks of a type
+    fn impls(&self) -> &[Id] {
+        match self.item().map(|item| &item.inner) {
+            Some(ItemEnum::Struct(struct_)) => &struct_.impls,
+            Some(ItemEnum::Enum(enum_)) => &enum_.impls,
+            Some(ItemEnum::Union(union_)) => &union_.impls,
+            _ => &[],
+        }
+    }
+
+    pub fn trait_impls(&self) -> Vec<&Impl> {
+        let crate_ = self.pool.crates.get(&self.id.pkg).unwrap();
+        self.impls()
+            .iter()
+            .filter_map(|id| crate_.index.get(id))
+            .filter(|item| {
+                self.pool.options.include_derived_impls
+                    || !item
+                        .attrs
+                        .iter()
+                        .any(|attr| attr == "#[automatically_derived]")
+            })
+            .filter_map(|item| match &item.inner {
+                ItemEnum::Impl(impl_) if impl_.trait_.is_some() => Some(impl_),
+                _ => None,
+            })
+            .filter(|impl_| {
+                self.pool.options.include_synthetic_impls
+                    || !(impl_.synthetic || impl_.blanket_impl.is_some())
+            })
+            .collect()
+    }
+
+    // Public fields of a struct or union, tuple struct fields being named by their position
+    pub fn fields(&self) -> Vec<Rc<CachedItem>> {
+        let ids: Vec<&Id> = match self.item().map(|item| &item.inner) {
+            Some(ItemEnum::Struct(struct_)) => match &struct_.kind {
+                StructKind::Unit => vec![],
+                // Private tuple fields are stripped, leaving their positions empty
+                StructKind::Tuple(fields) => fields.iter().flatten().collect(),
+                StructKind::Plain { fields, .. } => fields.iter().collect(),
+            },
+            Some(ItemEnum::Union(union_)) => union_.fields.iter().collect(),
+            _ => vec![],
+        };
+        (ids.into_iter())
+            .map(|id| self.pool.clone().get(&ItemId::new(&self.id.pkg, id)))
+            .collect()
+    }
+
+    // Variants of an enum
+    pub fn variants(&self) -> Vec<Rc<CachedItem>> {
+        match self.item().map(|item| &item.inner) {
+            Some(ItemEnum::Enum(enum_)) => (enum_.variants.iter())
+                .map(|id| self.pool.clone().get(&ItemId::new(&self.id.pkg, id)))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    // Items defined directly in a module, without submodules which are extracted on their own
+    pub fn module_items(&self) -> Vec<Rc<CachedItem>> {
+        let crate_ = self.pool.crates.get(&self.id.pkg).unwrap();
+        match (self.item().map(|item| &item.inner), self.item_summary()) {
+            (Some(ItemEnum::Module(module)), Some(module_summ)) => (module.items.iter())
+                .filter(|id| {
+                    crate_.paths.get(id).is_some_and(|summ| {
+                        MODULE_ITEM_KINDS.contains(&summ.kind)
+                            && summ.path.split_last().map(|(_, parent)| parent)
+                                == Some(module_summ.path.as_slice())
+                    })
+                })
+                .map(|id| self.pool.clone().get(&ItemId::new(&self.id.pkg, id)))
+                .collect(),
+            _ => vec![],
         }
     }
 
@@ -261,20 +1082,17 @@ impl CachedItem {
             .get(&self.id.id)
     }
 
+    // Items neither indexed nor listed in the paths of their crate, e.g. private types in public
+    // signatures
+    pub fn is_missing(&self) -> bool {
+        self.item().is_none() && self.item_summary().is_none()
+    }
+
+    // Missing items are neither linked nor rendered, see `link_to` and `render_page`
     pub fn kind(&self) -> &ItemKind {
-        self.item_summary()
-            .map(|summ| &summ.kind)
-            .unwrap_or_else(|| match self.item() {
-                Some(item) => match &item.inner {
-                    ItemEnum::Function(_) => &ItemKind::Function,
-                    ItemEnum::Enum(_) => &ItemKind::Enum,
-                    ItemEnum::Struct(_) => &ItemKind::Struct,
-                    _ => {
-                        unimplemented!()
-                    }
-                },
-                None => unreachable!(),
-            })
+        (self.item_summary().map(|summ| &summ.kind))
+            .or(self.item().map(item_kind))
+            .expect("item missing from its crate")
     }
 
     pub fn name(&self) -> &str {
@@ -284,14 +1102,34 @@ impl CachedItem {
                 .item_summary()
                 .and_then(|summ| summ.path.last())
                 .map(|name| name.as_str()))
-            .unwrap()
+            // Some items, e.g. impl blocks, have no name at all
+            .unwrap_or(&self.id.id.0)
+    }
+
+    // Record a construct the renderer does not support, and render a placeholder in its place
+    pub fn unsupported(&self, construct: String) -> String {
+        eprintln!(
+            "warning: unsupported {} in `{}`",
+            construct,
+            self.path().join("::")
+        );
+        *self
+            .pool
+            .unsupported
+            .borrow_mut()
+            .entry(construct)
+            .or_default() += 1;
+        "…".to_string()
     }
 
-    fn path(&self) -> Vec<&str> {
-        if let Some(path) = self
-            .item_summary()
-            .map(|summ| summ.path.as_ref())
-            .or(self.path.as_ref())
+    pub fn crate_name(&self) -> &str {
+        self.path().first().cloned().unwrap()
+    }
+
+    // Explicitly given path takes precedence, e.g. for items placed under a re-export.
+    pub fn path(&self) -> Vec<&str> {
+        if let Some(path) = (self.path.as_ref())
+            .or(self.item_summary().map(|summ| summ.path.as_ref()))
             .map(|path| path.iter().map(|p| p.as_str()))
         {
             path.collect()
@@ -308,24 +1146,36 @@ impl CachedItem {
 
 impl CachedItem {
     fn html_root_url(&self) -> String {
-        let root_url = (self.item().map(|item| item.crate_id))
-            .or(self.item_summary().map(|summ| summ.crate_id))
-            .and_then(|ext_crate_id| {
-                (self.pool.crates)
-                    .get(&self.id.pkg)
-                    .map(|crate_| (crate_, ext_crate_id))
-            })
+        if SYSROOT_CRATES.contains(&self.crate_name()) {
+            return self.pool.sysroot_root_url();
+        }
+
+        let ext_crate_id = (self.item().map(|item| item.crate_id))
+            .or(self.item_summary().map(|summ| summ.crate_id));
+        let root_url = (self.pool.crates.get(&self.id.pkg))
+            .zip(ext_crate_id)
             .and_then(|(crate_, ext_crate_id)| crate_.external_crates.get(&ext_crate_id))
             .and_then(|ext_crate| ext_crate.html_root_url.as_deref());
 
+        let link_version = self.pool.options.link_version;
+
         match root_url {
+            Some(url) if link_version == LinkVersion::Latest => {
+                let re_docs_rs =
+                    RegexBuilder::new(r"^(?<crate_root>https://docs\.rs/[^/]+/)[^/]+/$")
+                        .build()
+                        .unwrap();
+                re_docs_rs.replace(url, "${crate_root}latest/").to_string()
+            }
             Some(url) => url.to_string(),
             None => {
                 let pkg = self.path().first().cloned().unwrap();
-                if self.pool.crates.keys().any(|k| k == pkg) {
+                if self.pool.crates.keys().any(|k| k == pkg) && link_version == LinkVersion::Pinned
+                {
+                    // Path and git dependencies may have no version to pin
                     let crate_version = (self.pool.crates.get(&self.id.pkg))
-                        .map(|crate_| crate_.crate_version.as_deref().unwrap())
-                        .unwrap();
+                        .and_then(|crate_| crate_.crate_version.as_deref())
+                        .unwrap_or("latest");
                     format!("https://docs.rs/{}/{}/", pkg, crate_version)
                 } else {
                     // For external crates
@@ -337,47 +1187,1224 @@ impl CachedItem {
 
     pub fn external_link(&self) -> &str {
         self.external_link.get_or_init(|| {
-            format!(
-                "{}{}/{}.{}.html",
-                self.html_root_url(),
-                self.path()
-                    .split_last()
-                    .map(|(_, path)| path.join("/"))
-                    .unwrap(),
-                serde_plain::to_string(self.kind()).unwrap(),
-                self.name()
-            )
+            // Associated items are documented on the page of their parent
+            if self.item_summary().is_none() {
+                if let Some(parent) = self.parent() {
+                    let anchor = match self.item().map(|item| &item.inner) {
+                        Some(ItemEnum::Function(func)) if !func.has_body => "tymethod",
+                        Some(ItemEnum::AssocConst { .. }) => "associatedconstant",
+                        Some(ItemEnum::AssocType { .. }) => "associatedtype",
+                        _ => "method",
+                    };
+                    return format!("{}#{}.{}", parent.external_link(), anchor, self.name());
+                }
+            }
+
+            let template =
+                (self.pool.options.link_template.as_deref()).unwrap_or(DEFAULT_LINK_TEMPLATE);
+            template
+                .replace("{base}", &self.html_root_url())
+                .replace(
+                    "{path}",
+                    &self
+                        .path()
+                        .split_last()
+                        .map(|(_, path)| path.join("/"))
+                        .unwrap(),
+                )
+                .replace("{kind}", &url_kind(self.kind()))
+                .replace("{name}", self.name())
         })
     }
 
+    // Type or trait an associated item belongs to, through the impl block or trait containing it
+    pub fn parent(&self) -> Option<Rc<CachedItem>> {
+        let crate_ = self.pool.crates.get(&self.id.pkg).unwrap();
+        let parent = crate_.index.values().find_map(|item| match &item.inner {
+            ItemEnum::Impl(impl_) if impl_.items.contains(&self.id.id) => match &impl_.for_ {
+                Type::ResolvedPath(path) => Some(&path.id),
+                _ => None,
+            },
+            ItemEnum::Trait(trait_) if trait_.items.contains(&self.id.id) => Some(&item.id),
+            _ => None,
+        })?;
+
+        Some(self.pool.clone().get(&ItemId::new(&self.id.pkg, parent)))
+    }
+
+    // Path of the page of this item below the output root
+    pub fn page_path(&self) -> Vec<String> {
+        let kind = serde_plain::to_string(self.kind()).unwrap();
+        // Pages of modules are the index of their directory
+        let index = (self.kind() == &ItemKind::Module).then_some("index");
+        let package = (self.pool.options.prefix_with_package).then(|| self.id.pkg.clone());
+        (package.into_iter())
+            .chain((self.pool.kind_dirs.iter()).map(|dir| dir.replace("{kind}", &kind)))
+            .chain(self.path().into_iter().chain(index).map(|p| p.to_string()))
+            .collect()
+    }
+
     fn relative_to(&self, other: &Self) -> Vec<String> {
-        let left = self.path();
+        let left = self.page_path();
+        let left = left.iter().map(|p| p.as_str()).collect::<Vec<&str>>();
         let left = (left.split_last().map(|(_, path)| path)).unwrap();
-        let right = other.path();
+        let right = other.page_path();
+        let right = right.iter().map(|p| p.as_str()).collect::<Vec<&str>>();
         let right = (right.split_last().map(|(_, path)| path)).unwrap();
-        let d = zip(left, right).map(|(l, r)| (l == r) as usize).sum();
 
-        (0..(left.len() - d))
-            .map(|_| "..")
-            .chain(right.iter().cloned().skip(d))
-            .map(|p| p.to_string())
+        relative_path(left, right)
+    }
+
+    // Anchor of the page of this item in single file mode
+    pub fn anchor(&self) -> String {
+        self.page_path()
+            .join("-")
+            .chars()
+            // Ids are case-sensitive, and would collide for e.g. `Point` and `point` otherwise
+            .map(|c| match c.is_alphanumeric() {
+                true => c,
+                false => '-',
+            })
             .collect()
     }
 
     pub fn cross_ref(&self, to: &Self) -> String {
+        if self.pool.options.single_file.is_some() {
+            return format!("#{}", to.anchor());
+        }
+
         self.relative_to(to)
             .into_iter()
-            .map(|p| p.to_string())
-            .chain([format!("{}.md", to.name())])
+            .chain([format!("{}.md", to.page_path().last().unwrap())])
             .collect::<Vec<String>>()
             .join("/")
     }
 
+    // Link from the page of this item to `target`, to the generated page when the target is
+    // extracted as well.
+    pub fn link_to(&self, target: &CachedItem) -> Option<String> {
+        if target.is_missing() {
+            None
+        } else if self.pool.is_extracted(&target.id) {
+            Some(self.cross_ref(target))
+        } else if self.pool.links_to_crate(target.crate_name()) {
+            self.pool.record_external_crate(target);
+            Some(target.external_link().to_string())
+        } else {
+            None
+        }
+    }
+
+    // Link from the page of this item to a file referenced in its docs, which is relative to the
+    // package root, the parent of `src`, as in an included README. Links to files that do not
+    // exist, e.g. to rustdoc pages, are kept.
+    fn asset_link(&self, dest: &str) -> Option<String> {
+        let crate_ = self.pool.crates.get(&self.id.pkg).unwrap();
+        let source = &crate_.index.get(&crate_.root)?.span.as_ref()?.filename;
+        let package_root = match source.parent()? {
+            dir if dir.ends_with("src") => dir.parent()?,
+            dir => dir,
+        };
+        let source = self.pool.source_root.join(package_root).join(dest);
+        if !source.is_file() {
+            return None;
+        }
+        let asset = if self.pool.options.copy_assets {
+            let asset = (self.pool.output_root.join("assets").join(&self.id.pkg)).join(
+                (Path::new(dest).components())
+                    .filter(|component| matches!(component, Component::Normal(_)))
+                    .collect::<PathBuf>(),
+            );
+            let copied = asset
+                .parent()
+                .map(create_dir_all)
+                .unwrap_or(Ok(()))
+                .and_then(|_| copy(&source, &asset));
+            match copied {
+                Ok(_) => asset,
+                Err(err) => {
+                    eprintln!("warning: failed to copy `{}`: {}", source.display(), err);
+                    source
+                }
+            }
+        } else {
+            source
+        };
+        let page = match &self.pool.options.single_file {
+            Some(single_file) => (Path::new(single_file).iter())
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+            None => self.page_path(),
+        };
+        let page_dir = (self.pool.output_root.iter())
+            .map(|p| p.to_string_lossy().to_string())
+            .chain(page.split_last()?.1.iter().cloned())
+            .collect::<PathBuf>();
+
+        let from = absolute_components(&page_dir);
+        let to = absolute_components(&asset);
+        Some(
+            relative_path(
+                &from.iter().map(|p| p.as_str()).collect::<Vec<&str>>(),
+                &to.iter().map(|p| p.as_str()).collect::<Vec<&str>>(),
+            )
+            .join("/"),
+        )
+    }
+
     pub fn docs(&self) -> String {
-        hide_code_block_lines(
+        self.docs_on(self)
+    }
+
+    // Docs rendered on the page of `page`, which links are relative to, e.g. docs of impl blocks
+    // on the page of their type
+    pub fn docs_on(&self, page: &CachedItem) -> String {
+        let docs = hide_code_block_lines(
             self.item()
                 .and_then(|item| item.docs.as_deref())
                 .unwrap_or(""),
+            self.pool.options.keep_hidden_doctest_lines,
+        );
+        let docs = if self.pool.options.strip_leading_heading {
+            strip_leading_heading(&docs, self.name())
+        } else {
+            docs
+        };
+        // Target of an intra-doc link, or `Some(None)` for targets missing from the crate, e.g.
+        // items stripped from the rustdoc JSON
+        let link_target = |dest: &str| {
+            let links = &self.item()?.links;
+            let id = (links.get(dest)).or(links.get(dest.trim_matches('`')))?;
+            let crate_ = self.pool.crates.get(&self.id.pkg)?;
+            Some(
+                (crate_.index.contains_key(id) || crate_.paths.contains_key(id))
+                    .then(|| self.pool.clone().get(&ItemId::new(&self.id.pkg, id))),
+            )
+        };
+        let docs = resolve_reference_links(&docs, |dest| page.link_to(&*link_target(dest)??));
+        let docs = resolve_intra_doc_links(&docs, |dest| {
+            link_target(dest).map(|target| target.and_then(|target| page.link_to(&target)))
+        });
+        // Docs included with `#[doc = include_str!(..)]` are expanded already, but relative links
+        // in them still point to files next to the sources.
+        let docs = rewrite_relative_links(&docs, |dest| page.asset_link(dest));
+        // Escaped ahead of the alerts, which quote code blocks out of the reach of the fences
+        let escape_html = (self.pool.options.escape_raw_html)
+            .unwrap_or(self.pool.options.output_format != OutputFormat::Sphinx);
+        let docs = if escape_html {
+            escape_raw_html(&docs)
+        } else {
+            docs
+        };
+        let docs = if self.pool.options.section_alerts {
+            section_alerts(&docs, self.pool.options.output_format)
+        } else {
+            docs
+        };
+
+        match self.pool.options.output_format {
+            OutputFormat::Markdown | OutputFormat::Json => docs,
+            OutputFormat::Sphinx => escape_inline_code(&docs),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs::{copy, create_dir_all, read_to_string, write, File};
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    use rustdoc_types::{Id, ItemKind};
+
+    use crate::error::ExtractError;
+    use crate::schema::Page;
+    use crate::segment::{
+        build_crate, cache_path, split_output_path, BuildOptions, ItemId, SegmentCollections,
+    };
+    use crate::testing::{collections, config, fixture, temp_dir};
+    use crate::Package;
+
+    // Paths of the collected items, joined with `::`
+    fn item_paths(toml: &str, test: &str) -> Vec<String> {
+        let collections = collections(&temp_dir(test), toml);
+        (collections.items().into_iter())
+            .map(|item| item.path.join("::"))
+            .collect()
+    }
+
+    #[test]
+    fn items_take_the_place_of_packages() {
+        let paths = item_paths(
+            r#"
+items = ["fixture::shapes::Point", "fixture::utils::checksum"]
+
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+"#,
+            "items_take_the_place_of_packages",
+        );
+        assert_eq!(
+            paths,
+            [
+                "fixture::shapes::Point::new",
+                "fixture::shapes::Point::distance_to",
+                "fixture::shapes::Point",
+                "fixture::utils::checksum",
+            ]
+        );
+    }
+
+    #[test]
+    fn format_version_is_checked_before_parsing() {
+        let dir = temp_dir("format_version_is_checked_before_parsing");
+        // Not a crate of any version, but the format version is all there is to read
+        write(
+            dir.join("other.json"),
+            r#"{"format_version": 99, "index": []}"#,
+        )
+        .unwrap();
+        let toml = format!(
+            r#"
+require_format_version = 28
+
+[[packages]]
+name = "other"
+kind = "struct"
+json_path = {:?}
+"#,
+            dir.join("other.json").to_string_lossy()
+        );
+
+        let err = SegmentCollections::from_config(config(&dir, &toml)).unwrap_err();
+        assert!(matches!(
+            err,
+            ExtractError::FormatVersion {
+                found: 99,
+                required: 28,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn raw_html_is_escaped_outside_of_code() {
+        let collections = collections(
+            &temp_dir("raw_html_is_escaped_outside_of_code"),
+            r#"
+items = ["fixture::utils::read_raw"]
+section_alerts = true
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#,
+        );
+        let page = collections.render("fixture::utils::read_raw").unwrap();
+        assert!(page
+            .contains("&lt;table&gt;&lt;tr&gt;&lt;td&gt;raw&lt;/td&gt;&lt;/tr&gt;&lt;/table&gt;"));
+        // Code quoted by the alert is kept as written
+        assert!(page.contains("> let v: Vec<u8> = vec![];"));
+    }
+
+    #[test]
+    fn sphinx_admonitions_convert_inline_code() {
+        let collections = collections(
+            &temp_dir("sphinx_admonitions_convert_inline_code"),
+            r#"
+items = ["fixture::utils::read_raw"]
+section_alerts = true
+output_format = "sphinx"
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#,
+        );
+        let page = collections.render("fixture::utils::read_raw").unwrap();
+        assert!(page.contains(
+            "````{admonition} Safety\n:class: caution\n\nThe <code>ptr</code> must point to a <code>Vec&lt;u8&gt;</code>:\n\n```rust\nlet v: Vec<u8> = vec![];\n```\n````"
+        ));
+    }
+
+    #[test]
+    fn relative_links_to_existing_files_are_rewritten() {
+        let dir = temp_dir("relative_links_to_existing_files_are_rewritten");
+        write(dir.join("diagram.svg"), "<svg/>").unwrap();
+        let collections = collections(
+            &dir,
+            r#"
+items = ["fixture::utils::with_assets"]
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#,
+        );
+        let page = collections.render("fixture::utils::with_assets").unwrap();
+        assert!(page.contains("![diagram](../../../diagram.svg)"));
+        // Links of legacy rustdoc pages are no files next to the sources
+        assert!(page.contains("[legacy page](struct.Legacy.html)"));
+    }
+
+    #[test]
+    fn links_to_missing_items_are_plain_text() {
+        let collections = collections(
+            &temp_dir("links_to_missing_items_are_plain_text"),
+            r#"
+items = ["fixture::utils::broken"]
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#,
+        );
+        let page = collections.render("fixture::utils::broken").unwrap();
+        assert!(page.contains("Links to `Gone` and missing."));
+    }
+
+    #[test]
+    fn jupyter_book_toc_lists_the_written_pages() {
+        let dir = temp_dir("jupyter_book_toc");
+        let collections = collections(
+            &dir,
+            r#"
+summary = "jupyter-book"
+
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+"#,
+        );
+        collections.extract().unwrap();
+        let toc = read_to_string(dir.join("out/_toc.yml")).unwrap();
+        assert!(toc.starts_with("format: jb-book\nroot: index\nparts:\n"));
+        assert!(toc.contains("- caption: \"fixture::shapes\"\n  chapters:\n"));
+        assert!(toc.contains(
+            "  - file: fixture/shapes/Circle\n    sections:\n    - file: fixture/shapes/Circle/area\n"
+        ));
+        assert!(dir.join("out/index.md").exists());
+    }
+
+    #[test]
+    fn no_packages_collect_nothing() {
+        assert!(collections(&temp_dir("no_packages"), "packages = []").is_empty());
+
+        let unmatched = collections(
+            &temp_dir("no_matching_packages"),
+            r#"
+[[packages]]
+name = "fixture"
+kind = "union"
+json_path = "{fixture}"
+"#,
+        );
+        assert!(unmatched.is_empty());
+    }
+
+    #[test]
+    fn packages_without_kind_inherit_the_default() {
+        let mut paths = item_paths(
+            r#"
+default_kind = "trait"
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+
+[[packages]]
+name = "fixture"
+kind = "enum"
+json_path = "{fixture}"
+"#,
+            "default_kind",
+        );
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                "fixture::Area",
+                "fixture::Area::area",
+                "fixture::Area::describe",
+                "fixture::RawShape",
+                "fixture::Shape",
+                "fixture::ShapeKind"
+            ]
+        );
+    }
+
+    #[test]
+    fn broken_links_of_written_pages_are_reported() {
+        let dir = temp_dir("broken_links");
+        let collections = collections(
+            &dir,
+            r#"
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+"#,
+        );
+        collections.extract().unwrap();
+        assert_eq!(collections.check_links().unwrap(), []);
+
+        let page = dir.join("out/fixture/shapes/Point.md");
+        write(
+            &page,
+            "See [the circle](Circle.md) and [the square](Square.md).",
+        )
+        .unwrap();
+        assert_eq!(
+            collections.check_links().unwrap(),
+            [(page, "Square.md".to_string())]
+        );
+    }
+
+    #[test]
+    fn external_links_of_generic_items_have_no_generics() {
+        let collections = collections(
+            &temp_dir("external_links_of_generic_items"),
+            r#"
+output_format = "json"
+
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+"#,
+        );
+        for (path, url) in [
+            (
+                "fixture::shapes::Wrapper",
+                "fixture/shapes/struct.Wrapper.html",
+            ),
+            ("fixture::Buffer", "fixture/struct.Buffer.html"),
+            (
+                "fixture::shapes::Borrowed",
+                "fixture/shapes/struct.Borrowed.html",
+            ),
+        ] {
+            let page: Page = serde_json::from_str(&collections.render(path).unwrap()).unwrap();
+            assert_eq!(
+                page.external_link,
+                format!("https://docs.rs/fixture/0.1.0/{}", url)
+            );
+        }
+    }
+
+    #[test]
+    fn errors_can_be_matched_by_kind() {
+        let dir = temp_dir("errors_can_be_matched_by_kind");
+        let err = SegmentCollections::from_config(config(
+            &dir,
+            r#"
+items = ["fixture::Missing"]
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#,
+        ))
+        .unwrap_err();
+        assert!(
+            matches!(&err, ExtractError::Config(message) if message == "item `fixture::Missing` not found in the packages")
+        );
+
+        write(dir.join("broken.json"), "{").unwrap();
+        let toml = format!(
+            "[[packages]]\nname = \"broken\"\njson_path = {:?}\n",
+            dir.join("broken.json").to_string_lossy()
+        );
+        let err = SegmentCollections::from_config(config(&dir, &toml)).unwrap_err();
+        assert!(matches!(err, ExtractError::Json(_)));
+
+        let toml = format!(
+            "[[packages]]\nname = \"missing\"\njson_path = {:?}\n",
+            dir.join("missing.json").to_string_lossy()
+        );
+        let err = SegmentCollections::from_config(config(&dir, &toml)).unwrap_err();
+        assert!(matches!(err, ExtractError::Io(_)));
+    }
+
+    #[test]
+    fn reexports_of_dependencies_are_followed() {
+        let toml = r#"
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+follow_reexports = true
+
+[[packages]]
+name = "dep"
+kind = "static"
+json_path = "{dep}"
+"#;
+        let dir = temp_dir("reexports_of_dependencies");
+        collections(&dir, toml).extract().unwrap();
+        let page = read_to_string(dir.join("out/fixture/Widget.md")).unwrap();
+        assert!(page.starts_with("# Widget\n\nA widget."));
+
+        let paths = item_paths(
+            &toml.replace("follow_reexports = true", ""),
+            "reexports_not_followed",
+        );
+        assert!(!paths.contains(&"fixture::Widget".to_string()));
+    }
+
+    #[test]
+    fn items_without_a_name_are_named_by_id() {
+        let collections = collections(
+            &temp_dir("items_without_a_name"),
+            r#"
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+"#,
+        );
+        let impl_ =
+            (collections.pool.clone()).get(&ItemId::new("fixture", &Id("0:13".to_string())));
+        assert_eq!(impl_.name(), "0:13");
+    }
+
+    #[test]
+    fn glob_reexports_are_expanded() {
+        let toml = r#"
+[[packages]]
+name = "fixture"
+kind = "function"
+json_path = "{fixture}"
+module_path = "fixture"
+expand_glob_reexports = true
+
+[[packages]]
+name = "dep"
+kind = "static"
+json_path = "{dep}"
+"#;
+        let dir = temp_dir("glob_reexports");
+        collections(&dir, toml).extract().unwrap();
+        for (file, docs) in [
+            ("helper.md", "Helps the dependency."),
+            ("assist.md", "Assists the dependency."),
+        ] {
+            let page = read_to_string(dir.join("out/fixture").join(file)).unwrap();
+            assert!(page.ends_with(&format!("\n\n{}\n", docs)), "{}", page);
+        }
+        // Items defined in the package stay where they are
+        assert!(dir.join("out/fixture/utils/helper.md").exists());
+
+        let paths = item_paths(
+            &toml.replace("expand_glob_reexports = true", ""),
+            "glob_reexports_not_expanded",
+        );
+        assert!(!paths.contains(&"fixture::helper".to_string()));
+    }
+
+    #[test]
+    fn pages_are_routed_by_kind() {
+        let dir = temp_dir("pages_are_routed_by_kind");
+        let mut config = config(
+            &dir,
+            r#"
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+
+[[packages]]
+name = "fixture"
+kind = "function"
+json_path = "{fixture}"
+"#,
+        );
+        config.output_path = dir.join("{kind}s").to_string_lossy().to_string();
+        let collections = SegmentCollections::from_config(config).unwrap();
+        collections.extract().unwrap();
+
+        let page = read_to_string(dir.join("functions/fixture/utils/points.md")).unwrap();
+        assert!(page.contains(r#"<a href="../../../structs/fixture/shapes/Point.md">Point</a>"#));
+        let page = read_to_string(dir.join("structs/fixture/shapes/Point.md")).unwrap();
+        assert!(page.contains("| [new](../../../functions/fixture/shapes/Point/new.md) |"));
+        // Only the assets missing from the fixture sources are not found
+        let with_assets = dir.join("functions/fixture/utils/with_assets.md");
+        assert_eq!(
+            collections.check_links().unwrap(),
+            [
+                (with_assets.clone(), "diagram.svg".to_string()),
+                (with_assets, "struct.Legacy.html".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn module_trees_are_extracted() {
+        let dir = temp_dir("module_trees");
+        let collections = collections(
+            &dir,
+            r#"
+[[packages]]
+name = "fixture"
+kind = "module"
+module_path = "fixture/shapes"
+json_path = "{fixture}"
+"#,
+        );
+        collections.extract().unwrap();
+
+        let index = read_to_string(dir.join("out/fixture/shapes/index.md")).unwrap();
+        assert!(index.starts_with("# shapes\n\nGeometric shapes."));
+        assert!(index.contains("| [solid](solid/index.md) | Solid shapes. |"));
+        assert!(index.contains("| [Point](Point.md) | A point in the plane. |"));
+        let index = read_to_string(dir.join("out/fixture/shapes/solid/index.md")).unwrap();
+        assert!(index.contains("| [Sphere](Sphere.md) | A sphere. |"));
+        assert!(dir.join("out/fixture/shapes/solid/Sphere.md").exists());
+        assert!(dir.join("out/fixture/shapes/Point/new.md").exists());
+    }
+
+    #[test]
+    fn referenced_assets_are_copied() {
+        let dir = temp_dir("referenced_assets_are_copied");
+        write(dir.join("diagram.svg"), "<svg/>").unwrap();
+        let collections = collections(
+            &dir,
+            r#"
+items = ["fixture::utils::with_assets"]
+copy_assets = true
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#,
+        );
+        collections.extract().unwrap();
+
+        let asset = dir.join("out/assets/fixture/diagram.svg");
+        assert_eq!(read_to_string(asset).unwrap(), "<svg/>");
+        let page = read_to_string(dir.join("out/fixture/utils/with_assets.md")).unwrap();
+        assert!(page.contains("![diagram](../../assets/fixture/diagram.svg)"));
+    }
+
+    #[test]
+    fn method_links_of_a_single_file_are_anchors() {
+        let dir = temp_dir("method_links_of_a_single_file_are_anchors");
+        let collections = collections(
+            &dir,
+            r#"
+items = ["fixture::shapes::Point"]
+single_file = "api.md"
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#,
+        );
+        collections.extract().unwrap();
+
+        let page = read_to_string(dir.join("out/api.md")).unwrap();
+        assert!(page.contains(
+            "<a id=\"fixture-shapes-Point-new\"></a>\n\n[Point](#fixture-shapes-Point) › new"
+        ));
+        assert!(page.contains("| [new](#fixture-shapes-Point-new) | Creates a point. |"));
+        assert_eq!(collections.check_links().unwrap(), []);
+    }
+
+    #[test]
+    fn metadata_of_collected_items() {
+        let collections = collections(
+            &temp_dir("metadata_of_collected_items"),
+            r#"
+items = ["fixture::shapes::Point"]
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#,
+        );
+        let items = collections.items();
+        let point = items.iter().find(|item| item.name == "Point").unwrap();
+        assert_eq!(point.kind, ItemKind::Struct);
+        assert_eq!(point.path, ["fixture", "shapes", "Point"]);
+        assert_eq!(
+            point.external_link,
+            "https://docs.rs/fixture/0.1.0/fixture/shapes/struct.Point.html"
+        );
+        let new = items.iter().find(|item| item.name == "new").unwrap();
+        assert_eq!(new.kind, ItemKind::Function);
+        assert_eq!(
+            new.external_link,
+            "https://docs.rs/fixture/0.1.0/fixture/shapes/struct.Point.html#method.new"
+        );
+    }
+
+    #[test]
+    fn external_links_follow_the_link_template() {
+        let collections = collections(
+            &temp_dir("external_links_follow_the_link_template"),
+            r#"
+items = ["fixture::shapes::Point"]
+link_template = "https://docs.example.com/{path}/{name}-{kind}/"
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#,
+        );
+        let items = collections.items();
+        let point = items.iter().find(|item| item.name == "Point").unwrap();
+        assert_eq!(
+            point.external_link,
+            "https://docs.example.com/fixture/shapes/Point-struct/"
+        );
+        // Associated items link to anchors of the templated page of their parent
+        let new = items.iter().find(|item| item.name == "new").unwrap();
+        assert_eq!(
+            new.external_link,
+            "https://docs.example.com/fixture/shapes/Point-struct/#method.new"
+        );
+    }
+
+    #[test]
+    fn synthetic_impls_are_excluded_by_default() {
+        let toml = r#"
+items = ["fixture::shapes::Point"]
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#;
+        let trait_impls = |toml: &str| {
+            let collections = collections(&temp_dir("synthetic_impls_are_excluded"), toml);
+            let point = (collections.items.iter())
+                .find(|item| item.name() == "Point")
+                .unwrap();
+            let methods = (point.associated_methods().iter())
+                .map(|method| method.name().to_string())
+                .collect::<Vec<_>>();
+            // Auto trait and blanket impls contribute no methods either way
+            assert_eq!(methods, ["new", "distance_to"]);
+            point.trait_impls().len()
+        };
+        // `Display`, the derived `Debug` being left out as well
+        assert_eq!(trait_impls(toml), 1);
+        // `Display`, `Send` and the blanket `From<T>`
+        let toml = format!("include_synthetic_impls = true\n{}", toml);
+        assert_eq!(trait_impls(&toml), 3);
+    }
+
+    #[test]
+    fn selectors_pair_kinds_with_module_paths() {
+        let paths = item_paths(
+            r#"
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+
+[[packages.selectors]]
+kind = "struct"
+module_path = "fixture::shapes::solid"
+
+[[packages.selectors]]
+kind = "function"
+module_path = "fixture::utils"
+"#,
+            "selectors",
+        );
+        assert!(paths.contains(&"fixture::shapes::solid::Sphere".to_string()));
+        assert!(paths.contains(&"fixture::utils::helper".to_string()));
+        // Structs outside of `solid` and items of other kinds in `utils` are left out
+        assert!(!paths.contains(&"fixture::shapes::Point".to_string()));
+        assert!(!paths.contains(&"fixture::utils::ERRNO".to_string()));
+        assert!(paths
+            .iter()
+            .all(|path| path.starts_with("fixture::utils::") || path.ends_with("::Sphere")));
+    }
+
+    #[test]
+    fn method_table_links_point_to_written_pages() {
+        let dir = temp_dir("method_table_links_point_to_written_pages");
+        let toml = r#"
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+"#;
+        for output_path in ["out", "{kind}s"] {
+            let mut config = config(&dir, toml);
+            config.output_path = dir.join(output_path).to_string_lossy().to_string();
+            SegmentCollections::from_config(config)
+                .unwrap()
+                .extract()
+                .unwrap();
+        }
+
+        for (page, link) in [
+            ("out/fixture/shapes/Point.md", "Point/new.md"),
+            (
+                "structs/fixture/shapes/Point.md",
+                "../../../functions/fixture/shapes/Point/new.md",
+            ),
+        ] {
+            let page = dir.join(page);
+            let content = read_to_string(&page).unwrap();
+            assert!(content.contains(&format!("| [new]({}) | Creates a point. |", link)));
+            assert!(page.parent().unwrap().join(link).is_file());
+        }
+    }
+
+    #[test]
+    fn packages_sharing_module_names_are_prefixed() {
+        let dir = temp_dir("packages_sharing_module_names_are_prefixed");
+        let collections = collections(
+            &dir,
+            r#"
+prefix_with_package = true
+
+[[packages]]
+name = "fixture"
+kind = "function"
+json_path = "{fixture}"
+
+[[packages]]
+name = "dep"
+kind = "function"
+json_path = "{dep}"
+"#,
+        );
+        collections.extract().unwrap();
+
+        for (file, docs) in [
+            ("fixture/fixture/utils/helper.md", "Helps.\n"),
+            ("dep/dep/utils/helper.md", "Helps the dependency.\n"),
+        ] {
+            assert!(read_to_string(dir.join("out").join(file))
+                .unwrap()
+                .ends_with(docs));
+        }
+        // Links between pages of the package still resolve
+        let with_assets = dir.join("out/fixture/fixture/utils/with_assets.md");
+        assert_eq!(
+            collections.check_links().unwrap(),
+            [
+                (with_assets.clone(), "diagram.svg".to_string()),
+                (with_assets, "struct.Legacy.html".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn crates_without_version_link_to_the_latest_docs() {
+        let collections = collections(
+            &temp_dir("crates_without_version_link_to_the_latest_docs"),
+            r#"
+[[packages]]
+name = "dep"
+kind = "struct"
+json_path = "{dep}"
+"#,
+        );
+        let items = collections.items();
+        let widget = items.iter().find(|item| item.name == "Widget").unwrap();
+        assert_eq!(
+            widget.external_link,
+            "https://docs.rs/dep/latest/dep/struct.Widget.html"
+        );
+    }
+
+    #[test]
+    fn linked_external_crates_are_recorded() {
+        let collections = collections(
+            &temp_dir("linked_external_crates_are_recorded"),
+            r#"
+items = ["fixture::shapes::Circle"]
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#,
+        );
+        assert!(collections.external_crates().is_empty());
+        collections.render("fixture::shapes::Circle").unwrap();
+
+        let crates = collections.external_crates();
+        // Linked from the type of `Circle::ignored`
+        assert_eq!(crates.get("serde"), Some(&Some("1.0.197".to_string())));
+        // Linked from the type of `Circle::label`, not published to docs.rs
+        assert_eq!(crates.get("std"), Some(&None));
+        assert!(!crates.contains_key("fixture"));
+    }
+
+    #[test]
+    fn cached_crates_are_reused_until_sources_change() {
+        let dir = temp_dir("cached_crates_are_reused_until_sources_change");
+        let manifest_path = dir.join("Cargo.toml");
+        // Builds fail for the missing toolchain, while cached crates are read without building
+        let options = BuildOptions {
+            manifest_path: manifest_path.to_str().unwrap(),
+            toolchain: "comment-extract-missing-toolchain",
+            cap_lints: "warn",
+            cache_dir: Some(&dir.join("cache")),
+            require_format_version: None,
+        };
+        let package: Package = toml::from_str(r#"name = "fixture""#).unwrap();
+        // The sources of the fixture are `src/lib.rs` next to the manifest
+        create_dir_all(dir.join("src")).unwrap();
+        write(
+            &manifest_path,
+            "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        write(
+            dir.join("src/lib.rs"),
+            "#![doc = include_str!(\"../README.md\")]\n",
         )
+        .unwrap();
+        write(dir.join("README.md"), "A fixture.").unwrap();
+        let cache = cache_path(&package, options).unwrap();
+        create_dir_all(cache.parent().unwrap()).unwrap();
+        copy(fixture("fixture"), &cache).unwrap();
+
+        let set_modified = |path: &Path, time: SystemTime| {
+            File::options()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_modified(time))
+                .unwrap()
+        };
+        let is_built = || match build_crate(&package, options) {
+            Ok(_) => false,
+            Err(ExtractError::Build { .. }) => true,
+            Err(err) => panic!("{}", err),
+        };
+        let now = SystemTime::now();
+        let files = [
+            &manifest_path,
+            &dir.join("src/lib.rs"),
+            &dir.join("README.md"),
+        ];
+        for file in files {
+            set_modified(file, now - Duration::from_secs(60));
+        }
+        set_modified(&cache, now);
+        assert!(!is_built());
+
+        // Packages with sources, or files included by them, modified after their JSON was cached
+        // are built again
+        for file in files {
+            set_modified(file, now + Duration::from_secs(60));
+            assert!(is_built(), "{}", file.display());
+            set_modified(file, now - Duration::from_secs(60));
+        }
+        assert!(!is_built());
+
+        // Packages of other workspaces are cached apart
+        let other = BuildOptions {
+            manifest_path: "other/Cargo.toml",
+            ..options
+        };
+        assert_ne!(cache_path(&package, other), Some(cache));
+    }
+
+    #[test]
+    fn items_missing_from_the_index_fail_to_render() {
+        let dir = temp_dir("items_missing_from_the_index_fail_to_render");
+        // Private items of public paths are listed in the paths of a crate but not indexed
+        let mut crate_: serde_json::Value =
+            serde_json::from_str(&read_to_string(fixture("fixture")).unwrap()).unwrap();
+        crate_["paths"]["0:500"] =
+            serde_json::json!({"crate_id": 0, "path": ["fixture", "Hidden"], "kind": "struct"});
+        write(dir.join("hidden.json"), crate_.to_string()).unwrap();
+        let toml = format!(
+            "[[packages]]\nname = \"fixture\"\nkind = \"trait\"\njson_path = {:?}\n",
+            dir.join("hidden.json").to_string_lossy()
+        );
+        let collections = SegmentCollections::from_config(config(&dir, &toml)).unwrap();
+
+        let err = collections.render("fixture::Hidden").unwrap_err();
+        assert!(matches!(
+            err,
+            ExtractError::Render { item, message }
+                if item == "fixture::Hidden" && message == "item missing from the index of its crate"
+        ));
+    }
+
+    #[test]
+    fn reexports_of_configured_dependencies_keep_both_pages() {
+        let dep = r#"
+[[packages]]
+name = "dep"
+kind = "struct"
+json_path = "{dep}"
+"#;
+        let fixture = r#"
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+follow_reexports = true
+"#;
+        // Either package may be collected first
+        for (test, toml) in [
+            (
+                "reexports_of_configured_dependencies",
+                format!("{}{}", dep, fixture),
+            ),
+            (
+                "reexports_of_configured_dependencies_last",
+                format!("{}{}", fixture, dep),
+            ),
+        ] {
+            let dir = temp_dir(test);
+            collections(&dir, &toml).extract().unwrap();
+            for file in ["out/fixture/Widget.md", "out/dep/Widget.md"] {
+                let page = read_to_string(dir.join(file)).unwrap();
+                assert!(page.starts_with("# Widget\n\nA widget."));
+            }
+        }
+    }
+
+    #[test]
+    fn kinds_of_items_without_summary() {
+        let collections = collections(
+            &temp_dir("kinds_of_items_without_summary"),
+            r#"
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+"#,
+        );
+        let item =
+            |id: &str| (collections.pool.clone()).get(&ItemId::new("fixture", &Id(id.to_string())));
+        // The inherent impl of `Point` and its method `new`
+        assert_eq!(item("0:13").kind(), &ItemKind::Impl);
+        assert_eq!(item("0:14").kind(), &ItemKind::Function);
+        assert_eq!(
+            item("0:14").page_path(),
+            ["fixture", "shapes", "Point", "new"]
+        );
+    }
+
+    #[test]
+    fn types_missing_from_their_crate_are_not_linked() {
+        let dir = temp_dir("types_missing_from_their_crate_are_not_linked");
+        let mut crate_: serde_json::Value =
+            serde_json::from_str(&read_to_string(fixture("fixture")).unwrap()).unwrap();
+        // `Point::x` of a private type, which is neither indexed nor listed in the paths
+        crate_["index"]["0:11"]["inner"]["struct_field"] =
+            serde_json::json!({"resolved_path": {"name": "Secret", "id": "0:501", "args": null}});
+        write(dir.join("secret.json"), crate_.to_string()).unwrap();
+        let toml = format!(
+            "[[packages]]\nname = \"fixture\"\nkind = \"struct\"\njson_path = {:?}\n",
+            dir.join("secret.json").to_string_lossy()
+        );
+        let collections = SegmentCollections::from_config(config(&dir, &toml)).unwrap();
+
+        let page = collections.render("fixture::shapes::Point").unwrap();
+        assert!(page.contains("| x | Secret | Horizontal coordinate. |"));
+    }
+
+    #[test]
+    fn std_links_follow_the_link_version() {
+        let toml = r#"
+[[packages]]
+name = "fixture"
+kind = "struct"
+json_path = "{fixture}"
+"#;
+        let point = |toml: &str| {
+            collections(&temp_dir("std_links_follow_the_link_version"), toml)
+                .render("fixture::shapes::Point")
+                .unwrap()
+        };
+        for (toml, root) in [
+            (toml.to_string(), "https://doc.rust-lang.org/nightly/"),
+            (
+                format!("link_version = \"latest\"\n{}", toml),
+                "https://doc.rust-lang.org/stable/",
+            ),
+        ] {
+            let page = point(&toml);
+            assert!(page.contains(&format!(
+                "<a href=\"{}std/primitive.f64.html\">f64</a>",
+                root
+            )));
+            assert!(page.contains(&format!(
+                "<a href=\"{}std/fmt/trait.Display.html\">Display</a>",
+                root
+            )));
+            assert!(!page.contains("https://doc.rust-lang.org/std/"));
+        }
+    }
+
+    #[test]
+    fn unsupported_constructs_are_counted() {
+        let collections = collections(
+            &temp_dir("unsupported_constructs_are_counted"),
+            r#"
+items = ["fixture::utils::apply"]
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#,
+        );
+        let page = collections.render("fixture::utils::apply").unwrap();
+        // Rendered as placeholders, in the bound of `F` and the argument of `Box`
+        assert_eq!(page.matches('…').count(), 2);
+        assert_eq!(
+            collections.unsupported_constructs(),
+            BTreeMap::from([
+                ("GenericArgs::Parenthesized".to_string(), 1),
+                ("Higher-Rank Trait Bounds".to_string(), 1)
+            ])
+        );
+    }
+
+    #[test]
+    fn output_paths_split_at_the_kind_placeholder() {
+        assert_eq!(
+            split_output_path("docs/api"),
+            (PathBuf::from("docs/api"), vec![])
+        );
+        assert_eq!(
+            split_output_path("docs/{kind}s/api"),
+            (
+                PathBuf::from("docs"),
+                vec!["{kind}s".to_string(), "api".to_string()]
+            )
+        );
+        assert_eq!(
+            split_output_path("{kind}"),
+            (PathBuf::from("."), vec!["{kind}".to_string()])
+        );
+    }
+
+    #[test]
+    fn anchors_differing_in_case_do_not_collide() {
+        let dir = temp_dir("anchors_differing_in_case_do_not_collide");
+        let collections = collections(
+            &dir,
+            r#"
+items = ["fixture::shapes::Point", "fixture::shapes::point"]
+single_file = "api.md"
+
+[[packages]]
+name = "fixture"
+json_path = "{fixture}"
+"#,
+        );
+        collections.extract().unwrap();
+
+        let page = read_to_string(dir.join("out/api.md")).unwrap();
+        assert!(page.contains("<a id=\"fixture-shapes-Point\"></a>\n\n# Point"));
+        assert!(page.contains("<a id=\"fixture-shapes-point\"></a>\n\n# point"));
+        // The return type of `point` links to the struct
+        assert!(page.contains(" → <a href=\"#fixture-shapes-Point\">Point</a>"));
     }
 }
@@ -0,0 +1,143 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+// Table of contents written next to the pages. No other summary, e.g. an mdBook `SUMMARY.md`,
+// is generated, so the grouping below is not shared with an existing format.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Summary {
+    JupyterBook,
+}
+
+// A generated page and the pages nested under it, e.g. the methods of a struct.
+#[derive(Debug, PartialEq)]
+pub struct Chapter<'a> {
+    pub file: &'a [String],
+    pub sections: Vec<&'a [String]>,
+}
+
+// Group pages, given as path components without extension, by the module they belong to.
+pub fn group_by_module(files: &[Vec<String>]) -> BTreeMap<String, Vec<Chapter<'_>>> {
+    let files: BTreeSet<&[String]> = files.iter().map(|file| file.as_slice()).collect();
+    let mut modules: BTreeMap<String, Vec<Chapter>> = BTreeMap::new();
+
+    for file in &files {
+        let (_, parent) = file.split_last().unwrap();
+        if !files.contains(parent) {
+            let sections = (files.iter())
+                .filter(|section| section.split_last().map(|(_, p)| p) == Some(*file))
+                .cloned()
+                .collect();
+            modules
+                .entry(parent.join("::"))
+                .or_default()
+                .push(Chapter { file, sections });
+        }
+    }
+
+    modules
+}
+
+// Table of contents of Jupyter Book, with one part for each module.
+pub fn jupyter_book_toc(root: &str, files: &[Vec<String>]) -> String {
+    let mut toc = vec![
+        "format: jb-book".to_string(),
+        format!("root: {}", root),
+        "parts:".to_string(),
+    ];
+
+    for (module, chapters) in group_by_module(files) {
+        // JSON strings are double-quoted YAML scalars, with quotes and backslashes escaped
+        toc.push(format!(
+            "- caption: {}",
+            serde_json::to_string(&module).unwrap()
+        ));
+        toc.push("  chapters:".to_string());
+        for chapter in chapters {
+            toc.push(format!("  - file: {}", chapter.file.join("/")));
+            if !chapter.sections.is_empty() {
+                toc.push("    sections:".to_string());
+                for section in chapter.sections {
+                    toc.push(format!("    - file: {}", section.join("/")));
+                }
+            }
+        }
+    }
+
+    toc.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(paths: &[&str]) -> Vec<Vec<String>> {
+        (paths.iter())
+            .map(|path| path.split('/').map(|c| c.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn pages_are_grouped_by_module() {
+        let files = files(&["a/b/Foo", "a/b/Foo/new", "a/b/bar", "a/c/Baz"]);
+        let modules = group_by_module(&files);
+        assert_eq!(modules.keys().collect::<Vec<_>>(), ["a::b", "a::c"]);
+        assert_eq!(
+            modules["a::b"],
+            [
+                Chapter {
+                    file: &files[0],
+                    sections: vec![&files[1][..]],
+                },
+                Chapter {
+                    file: &files[2],
+                    sections: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn jupyter_book_toc_has_a_part_for_each_module() {
+        let files = files(&["a/b/Foo", "a/b/Foo/new", "a/c/Baz"]);
+        assert_eq!(
+            jupyter_book_toc("index", &files),
+            r#"format: jb-book
+root: index
+parts:
+- caption: "a::b"
+  chapters:
+  - file: a/b/Foo
+    sections:
+    - file: a/b/Foo/new
+- caption: "a::c"
+  chapters:
+  - file: a/c/Baz
+"#
+        );
+    }
+
+    #[test]
+    fn captions_are_escaped() {
+        let files = files(&[r#"a/say "hi": now/Foo"#]);
+        assert!(jupyter_book_toc("index", &files).contains(r#"- caption: "a::say \"hi\": now""#));
+    }
+}
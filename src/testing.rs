@@ -0,0 +1,60 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+// Helpers of the tests, which read the hand-written rustdoc JSON of `tests/fixtures` in place of
+// building packages with a nightly toolchain
+
+use std::env;
+use std::fs::{create_dir_all, remove_dir_all};
+use std::path::{Path, PathBuf};
+
+use crate::segment::SegmentCollections;
+use crate::Config;
+
+// Path of a rustdoc JSON fixture, e.g. `fixture` or `dep`
+pub fn fixture(name: &str) -> String {
+    format!(
+        "{}/tests/fixtures/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    )
+}
+
+// Empty directory of a test, left in place afterwards to inspect the output
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join("comment-extract-tests").join(name);
+    let _ = remove_dir_all(&dir);
+    create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Config writing below `dir/out`, with `{fixture}` and `{dep}` in `toml` replaced by the paths of
+// the fixtures
+pub fn config(dir: &Path, toml: &str) -> Config {
+    let toml = format!(
+        "manifest_path = {:?}\noutput_path = {:?}\n{}",
+        dir.join("Cargo.toml").to_string_lossy(),
+        dir.join("out").to_string_lossy(),
+        toml.replace("{fixture}", &fixture("fixture"))
+            .replace("{dep}", &fixture("dep"))
+    );
+    toml::from_str(&toml).unwrap()
+}
+
+pub fn collections(dir: &Path, toml: &str) -> SegmentCollections {
+    SegmentCollections::from_config(config(dir, toml)).unwrap()
+}
@@ -15,9 +15,16 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::fs;
+use std::io;
+use std::iter::zip;
+use std::path::Path;
+
 use regex::RegexBuilder;
 use rustdoc_types::Item;
 
+use crate::repr::OutputFormat;
+
 // Get first line of docs as caption
 pub fn caption(item: &Item) -> &str {
     let re = RegexBuilder::new(r"(?:^\s*\n*)*(?P<caption>^\w*.*)(?:\n?)$?")
@@ -33,59 +40,729 @@ pub fn caption(item: &Item) -> &str {
         .unwrap_or("")
 }
 
-// Remove lines starts with `#` in code blocks
-pub fn hide_code_block_lines(docs: &str) -> String {
-    let re_code = RegexBuilder::new(r"^```(?<rust_code>(rust(\s*|\s+.*)?)|\s*)?$")
+// Text fit into a cell of a Markdown table, where pipes would end the cell and line breaks the row
+pub fn table_cell(text: &str) -> String {
+    text.replace(['\r', '\n'], " ").replace('|', "\\|")
+}
+
+// Relative path from directory `from` to directory `to`, both given as path components
+pub fn relative_path(from: &[&str], to: &[&str]) -> Vec<String> {
+    // Only the common prefix is shared; components equal after the paths diverge,
+    // like `m` in `a/x/m` and `a/y/m`, still need to be walked out of and back into
+    let common = zip(from, to).take_while(|(l, r)| l == r).count();
+
+    (0..(from.len() - common))
+        .map(|_| "..")
+        .chain(to.iter().cloned().skip(common))
+        .map(|p| p.to_string())
+        .collect()
+}
+
+// Escape characters with special meaning in HTML
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// Kind of a line of Markdown with regard to fenced code blocks
+#[derive(Debug, PartialEq)]
+pub enum Fence<'a> {
+    // Opening fence of a code block, e.g. `fence` of ```` ```rust ```` being ```` ``` ```` and `info`
+    // being `rust`
+    Open { fence: &'a str, info: &'a str },
+    Close,
+    Code,
+    // Lines outside of code blocks, and fences of MyST directives holding Markdown, e.g. the
+    // admonitions of `section_alerts`
+    Text,
+}
+
+// Fenced code blocks of Markdown, followed line by line. Blocks open with ```` ``` ```` or `~~~`, and
+// only a bare fence of the same marker and at least the same length closes them.
+#[derive(Default)]
+pub struct FenceTracker {
+    code_block: Option<(char, usize)>,
+    directives: Vec<(char, usize)>,
+}
+
+impl FenceTracker {
+    pub fn next<'a>(&mut self, line: &'a str) -> Fence<'a> {
+        let indent = line.len() - line.trim_start().len();
+        let marker = line[indent..]
+            .chars()
+            .next()
+            .filter(|c| *c == '`' || *c == '~');
+        let fence = marker.and_then(|marker| {
+            let len = line[indent..].chars().take_while(|c| *c == marker).count();
+            let info = line[indent + len..].trim();
+            // Info strings of backtick fences have no backticks, e.g. ```` ```a``` ```` is inline code
+            (len >= 3 && !(marker == '`' && info.contains('`'))).then_some((
+                (marker, len),
+                &line[..indent + len],
+                info,
+            ))
+        });
+        let closes = |(marker, len): (char, usize)| matches!(fence, Some(((m, l), _, "")) if m == marker && l >= len);
+
+        if let Some(code_block) = self.code_block {
+            if closes(code_block) {
+                self.code_block = None;
+                return Fence::Close;
+            }
+            return Fence::Code;
+        }
+        match fence {
+            None => Fence::Text,
+            Some(_)
+                if self
+                    .directives
+                    .last()
+                    .is_some_and(|directive| closes(*directive)) =>
+            {
+                self.directives.pop();
+                Fence::Text
+            }
+            // Directives other than those of code, e.g. `{code-block}`, hold Markdown
+            Some((open, _, info)) if info.starts_with('{') && !info.starts_with("{code") => {
+                self.directives.push(open);
+                Fence::Text
+            }
+            Some((open, fence, info)) => {
+                self.code_block = Some(open);
+                Fence::Open { fence, info }
+            }
+        }
+    }
+
+    // Whether `line` is a fence or a line of a code block, which are left as they are
+    pub fn is_code(&mut self, line: &str) -> bool {
+        self.next(line) != Fence::Text
+    }
+}
+
+// Replace inline code spans outside of code blocks with HTML `<code>` elements, so their content
+// survives when the docs are embedded into HTML.
+pub fn escape_inline_code(docs: &str) -> String {
+    let re_inline = RegexBuilder::new(r"`(?<code>[^`]+)`").build().unwrap();
+
+    let mut fences = FenceTracker::default();
+
+    docs.lines()
+        .map(|line| {
+            if fences.is_code(line) {
+                line.to_string()
+            } else {
+                re_inline
+                    .replace_all(line, |cap: &regex::Captures| {
+                        format!("<code>{}</code>", escape_html(&cap["code"]))
+                    })
+                    .to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Message of the `#[diagnostic::on_unimplemented]` attribute, shown when a trait is not implemented
+pub fn on_unimplemented_message(attrs: &[String]) -> Option<String> {
+    let re_message = RegexBuilder::new(r#"\bmessage\s*=\s*"(?<message>(?:[^"\\]|\\.)*)""#)
         .build()
         .unwrap();
-    let re_show = RegexBuilder::new(r"^[^#].*|^#\[.*").build().unwrap();
 
-    enum CodeBlock {
-        Rust,
-        Others,
-        None,
+    attrs
+        .iter()
+        .filter(|attr| attr.starts_with("#[diagnostic::on_unimplemented"))
+        .find_map(|attr| re_message.captures(attr))
+        .map(|cap| cap["message"].replace("\\\"", "\""))
+}
+
+// Condition of the `#[cfg]` attribute of an item, e.g. `feature = "serde"`
+pub fn cfg_condition(attrs: &[String]) -> Option<String> {
+    attrs.iter().find_map(|attr| {
+        attr.strip_prefix("#[cfg(")
+            .and_then(|attr| attr.strip_suffix(")]"))
+            .map(|condition| condition.to_string())
+    })
+}
+
+// Write `content` to a temporary file next to `path` and rename it into place, so a failed run
+// never leaves a partially written file behind
+pub fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, content)
+        .and_then(|_| fs::rename(&tmp, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+}
+
+// Remove the first line of docs if it is a level one heading of `name`, e.g. `# Name` or
+// `` # `Name` ``
+pub fn strip_leading_heading(docs: &str, name: &str) -> String {
+    let mut lines = docs
+        .lines()
+        .skip_while(|line| line.trim().is_empty())
+        .peekable();
+    let is_duplicate = lines
+        .peek()
+        .and_then(|line| line.trim().strip_prefix("# "))
+        .is_some_and(|title| title.trim().trim_matches('`') == name);
+
+    if is_duplicate {
+        lines
+            .skip(1)
+            .skip_while(|line| line.trim().is_empty())
+            .collect::<Vec<&str>>()
+            .join("\n")
+    } else {
+        docs.to_string()
+    }
+}
+
+// Escape HTML tags outside of code, so they are shown as written. Autolinks, e.g.
+// `<https://docs.rs>`, are kept.
+pub fn escape_raw_html(docs: &str) -> String {
+    let re_tag = RegexBuilder::new(r"<(?<tag>/?[A-Za-z][A-Za-z0-9-]*(?:\s[^<>]*)?/?)>")
+        .build()
+        .unwrap();
+
+    let mut fences = FenceTracker::default();
+
+    docs.lines()
+        .map(|line| {
+            if fences.is_code(line) {
+                line.to_string()
+            } else {
+                // Segments at odd positions are inline code
+                line.split('`')
+                    .enumerate()
+                    .map(|(i, segment)| match i % 2 {
+                        0 => re_tag.replace_all(segment, "&lt;$tag&gt;").to_string(),
+                        _ => segment.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join("`")
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Turn `Panics`, `Safety` and `Errors` sections into GitHub alerts, or into admonitions for Sphinx
+pub fn section_alerts(docs: &str, format: OutputFormat) -> String {
+    let re_heading = RegexBuilder::new(r"^(?<level>#{1,6})\s+(?<title>.*?)\s*$")
+        .build()
+        .unwrap();
+
+    fn alert(title: &str) -> Option<&'static str> {
+        match title {
+            "Panics" => Some("warning"),
+            "Safety" => Some("caution"),
+            "Errors" => Some("important"),
+            _ => None,
+        }
     }
 
-    let mut filtered_docs: Vec<&str> = Vec::new();
-    let mut stat = CodeBlock::None;
+    fn flush(output: &mut Vec<String>, title: &str, lines: &[&str], format: OutputFormat) {
+        let kind = alert(title).unwrap();
+        let lines = lines
+            .iter()
+            .skip_while(|line| line.trim().is_empty())
+            .cloned()
+            .collect::<Vec<&str>>();
+        let end = (lines.iter())
+            .rposition(|line| !line.trim().is_empty())
+            .map(|pos| pos + 1)
+            .unwrap_or(0);
+
+        if output.last().is_some_and(|line| !line.is_empty()) {
+            output.push("".to_string());
+        }
+
+        match format {
+            OutputFormat::Markdown | OutputFormat::Json => {
+                output.push(format!("> [!{}]", kind.to_uppercase()));
+                output.push(format!("> **{}**", title));
+                output.push(">".to_string());
+                output.extend(
+                    lines[..end]
+                        .iter()
+                        .map(|line| format!("> {}", line).trim_end().to_string()),
+                );
+            }
+            OutputFormat::Sphinx => {
+                // Longer than the fences of code blocks in the section, which would close the
+                // admonition otherwise
+                let fence = "`".repeat(
+                    (lines.iter())
+                        .map(|line| line.trim_start().chars().take_while(|c| *c == '`').count())
+                        .fold(3, usize::max)
+                        + 1,
+                );
+                output.push(format!("{}{{admonition}} {}", fence, title));
+                output.push(format!(":class: {}", kind));
+                output.push("".to_string());
+                output.extend(lines[..end].iter().map(|line| line.to_string()));
+                output.push(fence);
+            }
+        }
+        output.push("".to_string());
+    }
+
+    let mut output = vec![];
+    // Title and heading level of the section being collected, and its lines
+    let mut section: Option<(&str, usize)> = None;
+    let mut section_lines = vec![];
+    let mut fences = FenceTracker::default();
 
     for line in docs.lines() {
-        match stat {
-            CodeBlock::Rust => {
-                if re_show.captures(line).is_some() {
-                    filtered_docs.push(line);
-                }
-                if re_code.captures(line).is_some() {
-                    stat = CodeBlock::None;
-                }
+        if fences.is_code(line) {
+        } else if let Some(cap) = re_heading.captures(line) {
+            let level = cap["level"].len();
+            if let Some((title, _)) = section.filter(|(_, section_level)| level <= *section_level) {
+                flush(&mut output, title, &section_lines, format);
+                section = None;
+                section_lines.clear();
             }
-            CodeBlock::Others => {
-                filtered_docs.push(line);
-                if re_code.captures(line).is_some() {
-                    stat = CodeBlock::None;
-                }
+
+            let title = cap.name("title").unwrap().as_str();
+            if section.is_none() && alert(title).is_some() {
+                section = Some((title, level));
+                continue;
             }
-            CodeBlock::None => {
-                if let Some(cap) = re_code.captures(line) {
-                    stat = match cap.name("rust_code") {
-                        Some(_) => {
-                            // The rustdoc code blocks without specifyinig a language would be `rust`, and
-                            // may contain additional attributes.
-                            // Replace with this line to work with Sphinix.
-                            filtered_docs.push("```rust");
-                            CodeBlock::Rust
+        }
+
+        match section {
+            Some(_) => section_lines.push(line),
+            None => output.push(line.to_string()),
+        }
+    }
+
+    if let Some((title, _)) = section {
+        flush(&mut output, title, &section_lines, format);
+    }
+
+    output.join("\n").trim_end().to_string()
+}
+
+// Rewrite destinations of reference-style links with `resolve`, which maps intra-doc link targets
+// to URLs. References to undefined labels are resolved as intra-doc links as well.
+pub fn resolve_reference_links<F>(docs: &str, resolve: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let re_definition =
+        RegexBuilder::new(r"^(?<label>\s{0,3}\[[^\]]+\]:\s*)(?<dest>\S+)(?<title>.*)$")
+            .build()
+            .unwrap();
+    let re_reference = RegexBuilder::new(r"\[(?<text>[^\]]*)\]\[(?<ref>[^\]]+)\]")
+        .build()
+        .unwrap();
+
+    let mut fences = FenceTracker::default();
+    let defined = docs
+        .lines()
+        .filter_map(|line| re_definition.captures(line))
+        .map(|cap| {
+            let label = cap["label"].trim().trim_end_matches(':').trim_end();
+            label[1..label.len() - 1].to_lowercase()
+        })
+        .collect::<Vec<String>>();
+
+    docs.lines()
+        .map(|line| {
+            if fences.is_code(line) {
+                line.to_string()
+            } else if let Some(cap) = re_definition.captures(line) {
+                match resolve(&cap["dest"]) {
+                    Some(url) => format!("{}{}{}", &cap["label"], url, &cap["title"]),
+                    None => line.to_string(),
+                }
+            } else {
+                re_reference
+                    .replace_all(line, |cap: &regex::Captures| {
+                        let reference = &cap["ref"];
+                        match resolve(reference) {
+                            Some(url) if !defined.contains(&reference.to_lowercase()) => {
+                                format!("[{}]({})", &cap["text"], url)
+                            }
+                            _ => cap[0].to_string(),
                         }
-                        _ => {
-                            filtered_docs.push(line);
-                            CodeBlock::Others
+                    })
+                    .to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Destinations of relative links in a rendered page, both Markdown and HTML ones, without their
+// fragments. URLs and anchors within the page are left out.
+pub fn relative_links(page: &str) -> Vec<String> {
+    let re_link = RegexBuilder::new(r#"\]\((?<md>[^)\s]+)[^)]*\)|href="(?<html>[^"]+)""#)
+        .build()
+        .unwrap();
+
+    let mut fences = FenceTracker::default();
+    let mut links = vec![];
+    for line in page.lines() {
+        if !fences.is_code(line) {
+            links.extend(
+                (re_link.captures_iter(line))
+                    .filter_map(|cap| cap.name("md").or(cap.name("html")))
+                    .map(|dest| dest.as_str().split('#').next().unwrap_or("").to_string())
+                    .filter(|dest| {
+                        !dest.is_empty()
+                            && !dest.contains("://")
+                            && !dest.starts_with('/')
+                            && !dest.starts_with("mailto:")
+                    }),
+            );
+        }
+    }
+    links
+}
+
+// Rewrite inline intra-doc links, e.g. `[text](Item)`, and shortcut ones, e.g. [`Item`], with
+// `resolve`, which maps the targets to `Some` URL, or to `None` when the target has no page to link
+// to and the link is left as plain text. Other links are kept as they are.
+pub fn resolve_intra_doc_links<F>(docs: &str, resolve: F) -> String
+where
+    F: Fn(&str) -> Option<Option<String>>,
+{
+    let re_link = RegexBuilder::new(
+        r"(?<image>!?)\[(?<text>[^\[\]]*)\](?:\((?<dest>[^)\s]+)\)|(?<next>[\[:]?))",
+    )
+    .build()
+    .unwrap();
+
+    let mut fences = FenceTracker::default();
+    docs.lines()
+        .map(|line| {
+            if fences.is_code(line) {
+                line.to_string()
+            } else {
+                re_link
+                    .replace_all(line, |cap: &regex::Captures| {
+                        let text = &cap["text"];
+                        // Images, reference links and link definitions are not intra-doc links
+                        let dest = match (cap.name("dest"), cap.name("next")) {
+                            _ if !cap["image"].is_empty() => None,
+                            (Some(dest), _) => Some(dest.as_str()),
+                            (None, Some(next)) if next.is_empty() => Some(text),
+                            _ => None,
+                        };
+                        match dest.and_then(&resolve) {
+                            Some(Some(url)) => format!("[{}]({})", text, url),
+                            Some(None) => text.to_string(),
+                            None => cap[0].to_string(),
                         }
-                    };
-                } else {
-                    filtered_docs.push(line);
-                };
+                    })
+                    .to_string()
             }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Rewrite relative file destinations of inline links, images and link definitions with
+// `rewrite`, leaving URLs, anchors and intra-doc links as they are
+pub fn rewrite_relative_links<F>(docs: &str, rewrite: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let re_definition =
+        RegexBuilder::new(r"^(?<label>\s{0,3}\[[^\]]+\]:\s*)(?<dest>\S+)(?<title>.*)$")
+            .build()
+            .unwrap();
+    let re_inline = RegexBuilder::new(r"(?<head>!?\[[^\]]*\]\()(?<dest>[^)\s]+)")
+        .build()
+        .unwrap();
+
+    // Fragments are kept as is
+    let rewrite_dest = |dest: &str| -> Option<String> {
+        let (path, fragment) = match dest.split_once('#') {
+            Some((path, fragment)) => (path, format!("#{}", fragment)),
+            None => (dest, "".to_string()),
+        };
+        let is_file = !path.is_empty()
+            && !path.contains("://")
+            && !path.contains("::")
+            && !path.starts_with('/')
+            && !path.starts_with("mailto:")
+            && (path.contains('/') || Path::new(path).extension().is_some());
+        if is_file {
+            rewrite(path).map(|path| format!("{}{}", path, fragment))
+        } else {
+            None
         }
+    };
+
+    let mut fences = FenceTracker::default();
+    docs.lines()
+        .map(|line| {
+            if fences.is_code(line) {
+                line.to_string()
+            } else if let Some(cap) = re_definition.captures(line) {
+                match rewrite_dest(&cap["dest"]) {
+                    Some(dest) => format!("{}{}{}", &cap["label"], dest, &cap["title"]),
+                    None => line.to_string(),
+                }
+            } else {
+                re_inline
+                    .replace_all(line, |cap: &regex::Captures| {
+                        match rewrite_dest(&cap["dest"]) {
+                            Some(dest) => format!("{}{}", &cap["head"], dest),
+                            None => cap[0].to_string(),
+                        }
+                    })
+                    .to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Remove lines starts with `#` in code blocks, unless `keep_hidden` is set
+pub fn hide_code_block_lines(docs: &str, keep_hidden: bool) -> String {
+    // Fences of other languages and MyST directives, e.g. ```` ```{note} ````, are kept as is
+    let re_rust_code = RegexBuilder::new(r"^(rust(\s*|\s+.*)?|\s*)$")
+        .build()
+        .unwrap();
+    let re_show = RegexBuilder::new(r"^[^#].*|^#\[.*").build().unwrap();
+
+    let mut fences = FenceTracker::default();
+    let mut in_rust_code = false;
+
+    docs.lines()
+        .filter_map(|line| match fences.next(line) {
+            Fence::Open { fence, info } => {
+                in_rust_code = re_rust_code.is_match(info);
+                // The rustdoc code blocks without specifyinig a language would be `rust`, and
+                // may contain additional attributes.
+                // Replace with this line to work with Sphinix.
+                Some(match in_rust_code {
+                    true => format!("{}rust", fence),
+                    false => line.to_string(),
+                })
+            }
+            Fence::Code if in_rust_code && !keep_hidden && !re_show.is_match(line) => None,
+            _ => Some(line.to_string()),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Name of the enum variant of a value, e.g. `RawPointer` for `Type::RawPointer { .. }`
+pub fn variant_name<T: std::fmt::Debug>(value: &T) -> String {
+    format!("{:?}", value)
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fences_close_with_the_same_marker() {
+        let docs = "~~~\n# use std::io;\n```\n# fn main() {}\n~~~\nText";
+        let lines = (docs.lines())
+            .scan(FenceTracker::default(), |fences, line| {
+                Some(fences.next(line))
+            })
+            .collect::<Vec<Fence>>();
+        assert_eq!(
+            lines,
+            [
+                Fence::Open {
+                    fence: "~~~",
+                    info: ""
+                },
+                Fence::Code,
+                Fence::Code,
+                Fence::Code,
+                Fence::Close,
+                Fence::Text,
+            ]
+        );
+        assert_eq!(
+            hide_code_block_lines(docs, false),
+            "~~~rust\n```\n~~~\nText"
+        );
+    }
+
+    #[test]
+    fn helpers_skip_tilde_code_blocks() {
+        let docs = "<b>\n~~~text\n<b> `Vec<T>` [`Item`]\n```\n~~~\n<b>";
+        assert_eq!(
+            escape_raw_html(docs),
+            "&lt;b&gt;\n~~~text\n<b> `Vec<T>` [`Item`]\n```\n~~~\n&lt;b&gt;"
+        );
+        assert_eq!(escape_inline_code(docs), docs);
+        assert_eq!(
+            resolve_intra_doc_links(docs, |_| Some(Some("Item.md".to_string()))),
+            docs
+        );
+    }
+
+    #[test]
+    fn safety_sections_become_alerts() {
+        let docs = "Reads.\n\n# Safety\n\nThe `ptr` must be valid:\n\n````\n```\n````\n\n# Examples\n\nNone.";
+        assert_eq!(
+            section_alerts(docs, OutputFormat::Markdown),
+            "Reads.\n\n> [!CAUTION]\n> **Safety**\n>\n> The `ptr` must be valid:\n>\n> ````\n> ```\n> ````\n\n# Examples\n\nNone."
+        );
+
+        let sphinx = section_alerts(docs, OutputFormat::Sphinx);
+        assert_eq!(
+            sphinx,
+            "Reads.\n\n`````{admonition} Safety\n:class: caution\n\nThe `ptr` must be valid:\n\n````\n```\n````\n`````\n\n# Examples\n\nNone."
+        );
+        // Admonitions hold Markdown, while the code blocks in them are left as they are
+        assert_eq!(
+            escape_inline_code(&sphinx),
+            sphinx.replace("The `ptr`", "The <code>ptr</code>")
+        );
+    }
+
+    #[test]
+    fn relative_file_links_are_rewritten() {
+        let docs = "![a](img/a.png#top) [b](https://docs.rs/b.html) [c](#c) [d](crate::D)\n\n[e]: e.md \"E\"\n\n```\n[f](f.md)\n```";
+        assert_eq!(
+            rewrite_relative_links(docs, |dest| Some(format!("../{}", dest))),
+            "![a](../img/a.png#top) [b](https://docs.rs/b.html) [c](#c) [d](crate::D)\n\n[e]: ../e.md \"E\"\n\n```\n[f](f.md)\n```"
+        );
+    }
+
+    #[test]
+    fn failed_writes_leave_no_partial_file() {
+        let dir = crate::testing::temp_dir("failed_writes_leave_no_partial_file");
+        // A directory in place of the file fails the rename
+        let path = dir.join("page.md");
+        fs::create_dir_all(path.join("nested")).unwrap();
+        assert!(write_atomic(&path, "# Page").is_err());
+        assert!(path.is_dir());
+        assert!(!dir.join("page.md.tmp").exists());
+
+        write_atomic(&dir.join("other.md"), "# Other").unwrap();
+        assert_eq!(fs::read_to_string(dir.join("other.md")).unwrap(), "# Other");
+        assert!(!dir.join("other.md.tmp").exists());
     }
 
-    filtered_docs.join("\n")
+    #[test]
+    fn sibling_modules_share_only_their_parent() {
+        assert_eq!(
+            relative_path(&["a", "x", "m"], &["a", "y", "m"]),
+            ["..", "..", "y", "m"]
+        );
+        assert_eq!(
+            relative_path(&["a", "ab", "b"], &["a", "abc", "b"]),
+            ["..", "..", "abc", "b"]
+        );
+    }
+
+    #[test]
+    fn relative_paths_between_directories() {
+        let empty: [&str; 0] = [];
+        assert_eq!(relative_path(&["a", "b"], &["a", "b"]), empty);
+        assert_eq!(relative_path(&["a"], &["a", "b"]), ["b"]);
+        assert_eq!(relative_path(&["a", "b"], &["a"]), [".."]);
+        assert_eq!(relative_path(&["a", "b"], &["a", "c"]), ["..", "c"]);
+        assert_eq!(
+            relative_path(&["x", "b", "c"], &["y", "b", "c"]),
+            ["..", "..", "..", "y", "b", "c"]
+        );
+        assert_eq!(relative_path(&[], &[]), empty);
+        assert_eq!(relative_path(&[], &["a"]), ["a"]);
+        assert_eq!(relative_path(&["a"], &[]), [".."]);
+    }
+
+    #[test]
+    fn inline_code_is_escaped_outside_of_code_blocks() {
+        assert_eq!(
+            escape_inline_code("Returns `Vec<T>` or `&str`.\n```\nlet v: Vec<u8> = `x`;\n```"),
+            "Returns <code>Vec&lt;T&gt;</code> or <code>&amp;str</code>.\n```\nlet v: Vec<u8> = `x`;\n```"
+        );
+    }
+
+    #[test]
+    fn hidden_lines_of_rust_code_blocks() {
+        let docs = "```\n# use std::io;\n#[derive(Debug)]\nstruct A;\n```\n```text\n# kept\n```";
+        assert_eq!(
+            hide_code_block_lines(docs, false),
+            "```rust\n#[derive(Debug)]\nstruct A;\n```\n```text\n# kept\n```"
+        );
+        assert_eq!(
+            hide_code_block_lines(docs, true),
+            "```rust\n# use std::io;\n#[derive(Debug)]\nstruct A;\n```\n```text\n# kept\n```"
+        );
+    }
+
+    #[test]
+    fn reference_links_resolve_intra_doc_targets() {
+        let resolve = |dest: &str| match dest {
+            "Foo" | "`Foo`" => Some("Foo.md".to_string()),
+            _ => None,
+        };
+        let docs = "See [the type][`Foo`], [bar][Bar] and [docs].\n\n[docs]: Foo \"Title\"\n[Bar]: https://bar.rs";
+        assert_eq!(
+            resolve_reference_links(docs, resolve),
+            "See [the type](Foo.md), [bar][Bar] and [docs].\n\n[docs]: Foo.md \"Title\"\n[Bar]: https://bar.rs"
+        );
+    }
+
+    #[test]
+    fn on_unimplemented_messages_are_unescaped() {
+        let attrs = [
+            "#[must_use]".to_string(),
+            r#"#[diagnostic::on_unimplemented(message = "`{Self}` is not a \"shape\"", label = "x")]"#
+                .to_string(),
+        ];
+        assert_eq!(
+            on_unimplemented_message(&attrs).as_deref(),
+            Some(r#"`{Self}` is not a "shape""#)
+        );
+        assert_eq!(on_unimplemented_message(&attrs[..1]), None);
+    }
+
+    #[test]
+    fn leading_headings_of_the_item_name_are_stripped() {
+        assert_eq!(
+            strip_leading_heading("\n# `Point`\n\nA point.", "Point"),
+            "A point."
+        );
+        assert_eq!(
+            strip_leading_heading("# Point\nA point.", "Point"),
+            "A point."
+        );
+        assert_eq!(
+            strip_leading_heading("# Points\n\nA point.", "Point"),
+            "# Points\n\nA point."
+        );
+        assert_eq!(
+            strip_leading_heading("A point.\n# Point", "Point"),
+            "A point.\n# Point"
+        );
+    }
+
+    #[test]
+    fn myst_directives_pass_through_next_to_rust_code() {
+        let docs =
+            "```{note}\n# Heading\n```\n```\n# use std::io;\nlet x = 1;\n```\n:::{tip}\n# Tip\n:::";
+        assert_eq!(
+            hide_code_block_lines(docs, false),
+            "```{note}\n# Heading\n```\n```rust\nlet x = 1;\n```\n:::{tip}\n# Tip\n:::"
+        );
+    }
+
+    #[test]
+    fn relative_links_of_pages() {
+        let page = "[a](Point.md#fields) [b](https://docs.rs) [c](#top)\n<a href=\"../Missing.md\">d</a>\n```\n[e](Code.md)\n```";
+        assert_eq!(relative_links(page), ["Point.md", "../Missing.md"]);
+    }
 }